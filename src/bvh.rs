@@ -0,0 +1,221 @@
+use crate::{
+    intersection::Intersection,
+    ray::Ray,
+    shape::{Bounds, Shape},
+    Num, EPSILON,
+};
+
+const BINS: usize = 12;
+const MAX_LEAF_SIZE: usize = 4;
+
+// Bounding volume hierarchy over a list of shapes, split by the surface
+// area heuristic. Shapes without finite bounds (planes, open cylinders) are
+// kept aside and always tested.
+#[derive(Clone)]
+pub(crate) struct Bvh {
+    nodes: Vec<Node>,
+    indices: Vec<usize>,
+    unbounded: Vec<usize>,
+    bounds: Bounds,
+}
+
+// Leaves cover `count` entries of `indices` from `start`. Interior nodes
+// have their left child right after them and the right one at `right`.
+#[derive(Clone)]
+struct Node {
+    bounds: Bounds,
+    start: usize,
+    count: usize,
+    right: usize,
+}
+
+struct Item {
+    index: usize,
+    bounds: Bounds,
+    centroid: [Num; 3],
+}
+
+impl Bvh {
+    pub(crate) fn build(shapes: &[Shape]) -> Bvh {
+        let mut items = vec![];
+        let mut unbounded = vec![];
+        let mut bounds = Bounds::empty();
+        for (index, shape) in shapes.iter().enumerate() {
+            let b = shape.world_bounds();
+            bounds = bounds.merge(&b);
+            if b.is_empty() {
+                continue;
+            }
+            if b.is_finite() {
+                // Flat shapes make flat boxes, which rounding can slip past.
+                let b = b.padded(EPSILON);
+                let c = b.centroid();
+                items.push(Item {
+                    index,
+                    bounds: b,
+                    centroid: [c.get_x(), c.get_y(), c.get_z()],
+                });
+            } else {
+                unbounded.push(index);
+            }
+        }
+
+        let mut bvh = Bvh {
+            nodes: vec![],
+            indices: Vec::with_capacity(items.len()),
+            unbounded,
+            bounds,
+        };
+        if !items.is_empty() {
+            bvh.build_node(&mut items);
+        }
+        bvh
+    }
+
+    // Bounds of everything, in the space the shapes' transforms map into.
+    pub(crate) fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    fn build_node(&mut self, items: &mut [Item]) -> usize {
+        let node = self.nodes.len();
+        let bounds = items.iter().fold(Bounds::empty(), |b, i| b.merge(&i.bounds));
+        self.nodes.push(Node {
+            bounds,
+            start: self.indices.len(),
+            count: 0,
+            right: 0,
+        });
+
+        match split(items, &bounds) {
+            Some(mid) => {
+                let (left, right) = items.split_at_mut(mid);
+                self.build_node(left);
+                let right = self.build_node(right);
+                self.nodes[node].right = right;
+            }
+            None => {
+                self.indices.extend(items.iter().map(|i| i.index));
+                self.nodes[node].count = items.len();
+            }
+        }
+        node
+    }
+
+    // Every intersection of the ray with the shapes whose boxes it passes
+    // through, in no particular order.
+    pub(crate) fn intersect(&self, shapes: &[Shape], ray: Ray) -> Vec<Intersection> {
+        let mut xs = vec![];
+        self.visit(ray, |i| xs.append(&mut shapes[i].intersect(ray)));
+        xs
+    }
+
+    // Calls `f` with the index of every shape whose box the ray passes through.
+    pub(crate) fn visit(&self, ray: Ray, mut f: impl FnMut(usize)) {
+        for &i in &self.unbounded {
+            f(i);
+        }
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let mut stack = vec![0];
+        while let Some(n) = stack.pop() {
+            let node = &self.nodes[n];
+            if !node.bounds.intersects(ray) {
+                continue;
+            }
+            if node.count > 0 {
+                for &i in &self.indices[node.start..node.start + node.count] {
+                    f(i);
+                }
+            } else {
+                stack.push(node.right);
+                stack.push(n + 1);
+            }
+        }
+    }
+}
+
+// Partitions `items` at the cheapest binned SAH split, or returns None
+// when a leaf is cheaper.
+fn split(items: &mut [Item], bounds: &Bounds) -> Option<usize> {
+    if items.len() <= 1 {
+        return None;
+    }
+
+    // Cost, axis and first right hand bin of the best split so far, with
+    // the binning used on that axis.
+    let mut best: Option<(Num, usize, usize, Num, Num)> = None;
+    for axis in 0..3 {
+        let lo = items.iter().map(|i| i.centroid[axis]).fold(Num::INFINITY, Num::min);
+        let hi = items.iter().map(|i| i.centroid[axis]).fold(Num::NEG_INFINITY, Num::max);
+        if hi - lo <= 0.0 {
+            continue;
+        }
+        let scale = BINS as Num / (hi - lo);
+
+        let mut counts = [0usize; BINS];
+        let mut boxes = [Bounds::empty(); BINS];
+        for i in items.iter() {
+            let b = bin_of(i.centroid[axis], lo, scale);
+            counts[b] += 1;
+            boxes[b] = boxes[b].merge(&i.bounds);
+        }
+
+        // Sweep from the right to know the cost of every right half.
+        let mut right_area = [0.0; BINS];
+        let mut right_count = [0usize; BINS];
+        let (mut acc, mut n) = (Bounds::empty(), 0);
+        for b in (1..BINS).rev() {
+            acc = acc.merge(&boxes[b]);
+            n += counts[b];
+            right_area[b] = acc.surface_area();
+            right_count[b] = n;
+        }
+
+        let (mut acc, mut n) = (Bounds::empty(), 0);
+        for b in 1..BINS {
+            acc = acc.merge(&boxes[b - 1]);
+            n += counts[b - 1];
+            if n == 0 || right_count[b] == 0 {
+                continue;
+            }
+            let cost = acc.surface_area() * n as Num + right_area[b] * right_count[b] as Num;
+            if best.is_none_or(|(c, ..)| cost < c) {
+                best = Some((cost, axis, b, lo, scale));
+            }
+        }
+    }
+
+    let leaf_cost = bounds.surface_area() * items.len() as Num;
+    match best {
+        Some((cost, axis, bin, lo, scale)) if cost < leaf_cost || items.len() > MAX_LEAF_SIZE => {
+            let mid = partition(items, |i| bin_of(i.centroid[axis], lo, scale) < bin);
+            if mid == 0 || mid == items.len() {
+                None
+            } else {
+                Some(mid)
+            }
+        }
+        // All centroids coincide: no split separates them, but huge
+        // leaves are still worse than an arbitrary halving.
+        None if items.len() > MAX_LEAF_SIZE => Some(items.len() / 2),
+        _ => None,
+    }
+}
+
+fn bin_of(centroid: Num, lo: Num, scale: Num) -> usize {
+    (((centroid - lo) * scale) as usize).min(BINS - 1)
+}
+
+fn partition<T>(items: &mut [T], pred: impl Fn(&T) -> bool) -> usize {
+    let mut mid = 0;
+    for i in 0..items.len() {
+        if pred(&items[i]) {
+            items.swap(i, mid);
+            mid += 1;
+        }
+    }
+    mid
+}
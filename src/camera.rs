@@ -1,10 +1,13 @@
-use crate::{Canvas, Matrix4x4, Num, Ray, Tuple, World};
+use crate::{
+    hit, sampler::Sampler, transformation::Transform, AmbientOcclusion, Bounds, Canvas, Integrator,
+    Matrix4x4, Num, ObjectId, Pixel, Ray, RayKind, Tuple, World, EPSILON,
+};
 
 pub struct Camera {
     hsize: i32,
     vsize: i32,
     field_of_view: Num,
-    transform: Matrix4x4,
+    transform: Transform,
     pixel_size: Num,
     half_width: Num,
     half_height: Num,
@@ -21,7 +24,7 @@ impl Camera {
             hsize,
             vsize,
             field_of_view: fov,
-            transform: Matrix4x4::identity(),
+            transform: Transform::identity(),
             pixel_size,
             half_height,
             half_width,
@@ -32,13 +35,13 @@ impl Camera {
         vsize: i32,
         field_of_view: T,
         transform: Matrix4x4,
-    ) -> Camera
+    ) -> Result<Camera, String>
     where
         T: Into<Num>,
     {
         let mut c = Camera::new(hsize, vsize, field_of_view);
-        c.transform = transform;
-        c
+        c.set_transform(transform)?;
+        Ok(c)
     }
 }
 
@@ -53,20 +56,51 @@ impl Camera {
         self.field_of_view
     }
     pub fn transform(&self) -> Matrix4x4 {
-        self.transform
+        self.transform.matrix()
     }
     pub fn pixel_size(&self) -> Num {
         self.pixel_size
     }
+    pub fn set_transform(&mut self, transform: Matrix4x4) -> Result<(), String> {
+        self.transform = Transform::new(transform)?;
+        Ok(())
+    }
+
+    // Looks along `direction` at the middle of `bounds`, backed off until the
+    // sphere around them fits the narrower side of the view.
+    pub fn frame(&mut self, bounds: &Bounds, direction: Tuple) -> Result<(), String> {
+        if bounds.is_empty() || !bounds.is_finite() {
+            return Err("only finite, non-empty bounds can be framed".to_string());
+        }
+        if direction.magnitude() < EPSILON {
+            return Err("framing needs a view direction".to_string());
+        }
+        let forward = direction.normalize();
+        let radius = (bounds.size().magnitude() / 2.0).max(EPSILON);
+        let half_angle = self.half_width.min(self.half_height).atan();
+        let to = bounds.centroid();
+        let from = to - forward * (radius / half_angle.sin());
+
+        let up = if forward.cross(&Tuple::vector(0, 1, 0)).magnitude() < EPSILON {
+            Tuple::vector(0, 0, 1)
+        } else {
+            Tuple::vector(0, 1, 0)
+        };
+        self.set_transform(Matrix4x4::view(from, to, up))
+    }
 
     pub fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
-        let xoffset = (px as Num + 0.5) * self.pixel_size();
-        let yoffset = (py as Num + 0.5) * self.pixel_size();
+        self.ray_for_pixel_offset(px, py, 0.5, 0.5)
+    }
+
+    pub fn ray_for_pixel_offset(&self, px: usize, py: usize, ox: Num, oy: Num) -> Ray {
+        let xoffset = (px as Num + ox) * self.pixel_size();
+        let yoffset = (py as Num + oy) * self.pixel_size();
 
         let world_x = self.half_width - xoffset;
         let world_y = self.half_height - yoffset;
 
-        let transform_inv = self.transform().inverse().unwrap();
+        let transform_inv = self.transform.inverse();
 
         let pixel = transform_inv * Tuple::point(world_x, world_y, -1);
         let origin = transform_inv * Tuple::point(0, 0, 0);
@@ -93,6 +127,63 @@ impl Camera {
 
         image
     }
+
+    pub fn render_with(&self, world: World, integrator: Integrator) -> Canvas {
+        let mut image = Canvas::with_dimesnions(self.hsize() as u32, self.vsize() as u32);
+        for x in 0..self.hsize() {
+            for y in 0..self.vsize() {
+                let color = match integrator {
+                    Integrator::Whitted { max_depth } => {
+                        let ray = self.ray_for_pixel(x as usize, y as usize);
+                        world.color_at(ray, max_depth)
+                    }
+                    Integrator::PathTracing(tracer) => {
+                        let mut sampler = Sampler::for_pixel(x as u32, y as u32);
+                        let mut sum = Pixel::black();
+                        for _ in 0..tracer.samples() {
+                            let (ox, oy) = (sampler.next(), sampler.next());
+                            let ray = self.ray_for_pixel_offset(x as usize, y as usize, ox, oy);
+                            sum = sum + tracer.trace(&world, ray, &mut sampler);
+                        }
+                        sum * (1.0 / tracer.samples() as Num)
+                    }
+                };
+                image.set(x as u32, y as u32, color);
+            }
+        }
+
+        image
+    }
+
+    pub fn render_ambient_occlusion(&self, world: World, ao: AmbientOcclusion) -> Canvas {
+        let mut image = Canvas::with_dimesnions(self.hsize() as u32, self.vsize() as u32);
+        image.fill(Pixel::white());
+        for x in 0..self.hsize() {
+            for y in 0..self.vsize() {
+                let ray = self.ray_for_pixel(x as usize, y as usize);
+                let xs = world.intersect_world_for(ray, RayKind::Camera);
+                if let Some(h) = hit(xs.clone()) {
+                    let comps = h.prepare_computations(ray, xs);
+                    let open = world.ambient_occlusion_at(comps.over_point(), comps.normalv(), ao);
+                    image.set(x as u32, y as u32, Pixel::rgb(open, open, open));
+                }
+            }
+        }
+
+        image
+    }
+
+    // Object seen through the centre of a pixel.
+    pub fn pick(&self, world: &World, x: usize, y: usize) -> Option<ObjectId> {
+        world.pick(self.ray_for_pixel(x, y))
+    }
+
+    // Object id output, indexed [y][x], for masks and picking in post.
+    pub fn render_object_ids(&self, world: &World) -> Vec<Vec<Option<ObjectId>>> {
+        (0..self.vsize() as usize)
+            .map(|y| (0..self.hsize() as usize).map(|x| self.pick(world, x, y)).collect())
+            .collect()
+    }
 }
 
 fn get_pixel_size(hsize: i32, vsize: i32, field_of_view: Num) -> (Num, Num, Num) {
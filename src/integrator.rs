@@ -0,0 +1,302 @@
+use crate::{
+    hit,
+    img::Pixel,
+    intersection::Computations,
+    light::{AreaLight, Light},
+    ray::{Ray, RayKind},
+    sampler::{self, Sampler},
+    tuple::Tuple,
+    world::World,
+    Num, PI,
+};
+
+#[derive(Clone, Copy)]
+pub enum Integrator {
+    Whitted { max_depth: i32 },
+    PathTracing(PathTracer),
+}
+
+impl Integrator {
+    pub fn whitted() -> Integrator {
+        Integrator::Whitted { max_depth: 5 }
+    }
+    pub fn path_tracing(samples: u32) -> Integrator {
+        Integrator::PathTracing(PathTracer::new(samples))
+    }
+}
+
+// Unbiased path tracer: Lambert + normalized Phong bounces, next event
+// estimation with MIS (power heuristic) against area lights and Russian
+// roulette. The ambient term is ignored since indirect light replaces it.
+//
+// Point lights deliver `intensity * PI * cos` irradiance without falloff so a
+// diffuse surface matches the diffuse term of the Whitted shading.
+#[derive(Clone, Copy)]
+pub struct PathTracer {
+    samples: u32,
+    max_depth: u32,
+    roulette_depth: u32,
+}
+
+impl PathTracer {
+    pub fn new(samples: u32) -> PathTracer {
+        PathTracer {
+            samples: samples.max(1),
+            max_depth: 64,
+            roulette_depth: 3,
+        }
+    }
+}
+
+impl PathTracer {
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+    pub fn max_depth(&self) -> u32 {
+        self.max_depth
+    }
+    pub fn roulette_depth(&self) -> u32 {
+        self.roulette_depth
+    }
+    pub fn set_samples(&mut self, val: u32) {
+        self.samples = val.max(1);
+    }
+    pub fn set_max_depth(&mut self, val: u32) {
+        self.max_depth = val;
+    }
+    pub fn set_roulette_depth(&mut self, val: u32) {
+        self.roulette_depth = val;
+    }
+
+    pub fn radiance(&self, world: &World, ray: Ray, seed: u64) -> Pixel {
+        let mut sampler = Sampler::new(seed);
+        self.trace(world, ray, &mut sampler)
+    }
+
+    pub(crate) fn trace(&self, world: &World, ray: Ray, sampler: &mut Sampler) -> Pixel {
+        let mut ray = ray;
+        let mut kind = RayKind::Camera;
+        let mut throughput = Pixel::white();
+        let mut radiance = Pixel::black();
+        let mut last_pdf = 0.0;
+        let mut specular = true;
+
+        for depth in 0..self.max_depth {
+            let xs = world.intersect_world_for(ray, kind);
+            let surface = hit(xs.clone());
+
+            if let Some(light @ Light::Area(area)) = world.light() {
+                if let Some(t) = area.intersect(ray) {
+                    if surface.as_ref().is_none_or(|h| t < h.t()) {
+                        let weight = if specular {
+                            1.0
+                        } else {
+                            power_heuristic(last_pdf, area_pdf(&area, t, ray.direction()))
+                        };
+                        radiance = radiance + throughput * light.intensity() * weight;
+                        break;
+                    }
+                }
+            }
+
+            let h = match surface {
+                Some(h) => h,
+                None => break,
+            };
+            let comps = h.prepare_computations(ray, xs);
+            let lobes = Lobes::new(&comps);
+            let total = lobes.total();
+            if total <= 0.0 {
+                break;
+            }
+
+            if lobes.diffuse + lobes.glossy > 0.0 {
+                radiance = radiance + throughput * self.direct(world, &comps, &lobes, sampler);
+            }
+
+            let pick = sampler.next() * total;
+            if pick < lobes.diffuse + lobes.glossy {
+                let wi = if pick < lobes.diffuse {
+                    sampler::cosine_hemisphere(comps.normalv(), sampler)
+                } else {
+                    let cos_alpha = sampler.next().powf(1.0 / (lobes.shininess + 1.0));
+                    sampler::around_axis(lobes.mirror_dir, cos_alpha, 2.0 * PI * sampler.next())
+                };
+                let cos = wi.dot(&comps.normalv());
+                let pdf = lobes.pdf(&comps, wi);
+                if cos <= 0.0 || pdf <= 0.0 {
+                    break;
+                }
+                throughput = throughput * lobes.eval(&comps, wi) * (cos / pdf);
+                ray = Ray::new(comps.over_point(), wi);
+                kind = RayKind::Reflection;
+                last_pdf = pdf;
+                specular = false;
+            } else if pick < lobes.diffuse + lobes.glossy + lobes.mirror {
+                throughput = throughput * total;
+                ray = Ray::new(comps.over_point(), comps.reflectv());
+                kind = RayKind::Reflection;
+                specular = true;
+            } else {
+                let direction = match comps.refracted_direction() {
+                    Some(d) => d,
+                    None => break,
+                };
+                throughput = throughput * total;
+                ray = Ray::new(comps.under_point(), direction);
+                kind = RayKind::Refraction;
+                specular = true;
+            }
+
+            if depth + 1 >= self.roulette_depth {
+                let q = throughput.r().max(throughput.g()).max(throughput.b()).min(0.95);
+                if q <= 0.0 || sampler.next() >= q {
+                    break;
+                }
+                throughput = throughput * (1.0 / q);
+            }
+        }
+
+        radiance
+    }
+
+    fn direct(
+        &self,
+        world: &World,
+        comps: &Computations,
+        lobes: &Lobes,
+        sampler: &mut Sampler,
+    ) -> Pixel {
+        let light = match world.light() {
+            Some(l) => l,
+            None => return Pixel::black(),
+        };
+        let receives_shadow = comps.object().flags().receives_shadow();
+        let point = comps.over_point();
+
+        match light {
+            Light::Point(_) => {
+                let wi = (light.position() - point).normalize();
+                let cos = wi.dot(&comps.normalv());
+                if cos <= 0.0 {
+                    return Pixel::black();
+                }
+                let transmission = if receives_shadow {
+                    world.transmission_between(point, light.position())
+                } else {
+                    Pixel::white()
+                };
+                lobes.eval(comps, wi) * light.intensity() * transmission * (PI * cos)
+            }
+            Light::Area(area) => {
+                let target = area.point_at(sampler.next(), sampler.next());
+                let v = target - point;
+                let distance = v.magnitude();
+                let wi = v.normalize();
+                let cos = wi.dot(&comps.normalv());
+                let light_pdf = area_pdf(&area, distance, wi);
+                if cos <= 0.0 || light_pdf <= 0.0 || !light_pdf.is_finite() {
+                    return Pixel::black();
+                }
+                let transmission = if receives_shadow {
+                    world.transmission_between(point, target)
+                } else {
+                    Pixel::white()
+                };
+                let weight = power_heuristic(light_pdf, lobes.pdf(comps, wi));
+                lobes.eval(comps, wi) * light.intensity() * transmission * (cos * weight / light_pdf)
+            }
+        }
+    }
+}
+
+// Scattering weights of the material at a hit. Diffuse and glossy share
+// whatever reflection and transparency leave over and are normalized so
+// their sum never exceeds one.
+struct Lobes {
+    color: Pixel,
+    shininess: Num,
+    mirror_dir: Tuple,
+    diffuse: Num,
+    glossy: Num,
+    mirror: Num,
+    refraction: Num,
+}
+
+impl Lobes {
+    fn new(comps: &Computations) -> Lobes {
+        let object = comps.object();
+        let material = object.material();
+        let reflective = material.reflective().clamp(0.0, 1.0);
+        let transparency = material.transparency().clamp(0.0, 1.0);
+
+        let (mut mirror, mut refraction) = (reflective, transparency);
+        if reflective > 0.0 && transparency > 0.0 {
+            let reflectance = comps.schlick();
+            mirror *= reflectance;
+            refraction *= 1.0 - reflectance;
+        }
+
+        let base = (1.0 - reflective - transparency).max(0.0);
+        let mut kd = material.diffuse().max(0.0);
+        let mut ks = material.specular().max(0.0);
+        if kd + ks > 1.0 {
+            let scale = 1.0 / (kd + ks);
+            kd *= scale;
+            ks *= scale;
+        }
+
+        let n = comps.normalv();
+        let eyev = comps.eyev();
+        Lobes {
+            color: material.color_at(&object, comps.over_point()),
+            shininess: material.shininess(),
+            mirror_dir: n * (2.0 * n.dot(&eyev)) - eyev,
+            diffuse: base * kd,
+            glossy: base * ks,
+            mirror,
+            refraction,
+        }
+    }
+
+    fn total(&self) -> Num {
+        self.diffuse + self.glossy + self.mirror + self.refraction
+    }
+
+    fn eval(&self, comps: &Computations, wi: Tuple) -> Pixel {
+        if wi.dot(&comps.normalv()) <= 0.0 {
+            return Pixel::black();
+        }
+        let lobe = self.mirror_dir.dot(&wi).max(0.0).powf(self.shininess);
+        let glossy = self.glossy * (self.shininess + 2.0) / (2.0 * PI) * lobe;
+        self.color * (self.diffuse / PI) + Pixel::white() * glossy
+    }
+
+    // Density of picking `wi` through lobe selection plus lobe sampling.
+    fn pdf(&self, comps: &Computations, wi: Tuple) -> Num {
+        let total = self.total();
+        let cos = wi.dot(&comps.normalv()).max(0.0);
+        let lobe = self.mirror_dir.dot(&wi).max(0.0).powf(self.shininess);
+        (self.diffuse * cos / PI + self.glossy * (self.shininess + 1.0) / (2.0 * PI) * lobe)
+            / total
+    }
+}
+
+// Solid angle density of sampling `direction` by picking a uniform point on
+// the light `distance` away.
+fn area_pdf(area: &AreaLight, distance: Num, direction: Tuple) -> Num {
+    let cos_light = area.normal().dot(&direction.normalize()).abs();
+    if cos_light <= 0.0 {
+        return 0.0;
+    }
+    distance * distance / (area.area() * cos_light)
+}
+
+fn power_heuristic(a: Num, b: Num) -> Num {
+    let (a2, b2) = (a * a, b * b);
+    if a2 + b2 <= 0.0 {
+        0.0
+    } else {
+        a2 / (a2 + b2)
+    }
+}
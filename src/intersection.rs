@@ -1,27 +1,35 @@
-use crate::{ray::Ray, shape::Shape, tuple::Tuple, Num, EPSILON};
+use crate::{ray::Ray, shape::Shape, tuple::Tuple, world::ObjectId, Num, EPSILON};
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct Intersection {
     t: Num,
     obj: Shape,
+    u: Num,
+    v: Num,
+    object_id: Option<ObjectId>,
 }
 
 impl Intersection {
     pub fn new<T>(t: T, obj: Shape) -> Intersection
+    where
+        T: Into<Num>,
+    {
+        Intersection::with_uv(t, obj, 0.0, 0.0)
+    }
+    pub fn with_uv<T>(t: T, obj: Shape, u: Num, v: Num) -> Intersection
     where
         T: Into<Num>,
     {
         Intersection {
             t: t.into(),
-            obj: obj,
+            obj,
+            u,
+            v,
+            object_id: None,
         }
     }
     pub fn make(i1: Intersection, i2: Intersection) -> Vec<Intersection> {
-        let mut v = Vec::new();
-        v.push(i1);
-        v.push(i2);
-
-        v
+        vec![i1, i2]
     }
 }
 
@@ -30,7 +38,22 @@ impl Intersection {
         self.t
     }
     pub fn object(&self) -> Shape {
-        self.obj
+        self.obj.clone()
+    }
+    pub fn u(&self) -> Num {
+        self.u
+    }
+    pub fn v(&self) -> Num {
+        self.v
+    }
+    // The `World` object that was hit, or None when a shape was intersected
+    // on its own. Every hit inside a group carries the group's id.
+    pub fn object_id(&self) -> Option<ObjectId> {
+        self.object_id
+    }
+    pub(crate) fn with_object_id(mut self, id: ObjectId) -> Intersection {
+        self.object_id = Some(id);
+        self
     }
 }
 
@@ -40,9 +63,12 @@ impl Intersection {
 
         comps.set_t(self.t());
         comps.set_object(self.object());
+        comps.set_object_id(self.object_id());
         comps.set_point(ray.position(comps.t()));
         comps.set_eyev(-ray.direction());
-        comps.set_normalv(comps.object().normal_at(comps.point()));
+        // Hits always land on primitives, never on a group, so the fallback
+        // of facing the eye is only there to avoid a panic.
+        comps.set_normalv(comps.object().normal_at_hit(comps.point(), self).unwrap_or(comps.eyev()));
 
         if comps.normalv().dot(&comps.eyev()) < 0.0 {
             comps.set_inside(true);
@@ -55,28 +81,30 @@ impl Intersection {
         comps.set_over_point(comps.point() + comps.normalv() * EPSILON);
         comps.set_under_point(comps.point() - comps.normalv() * EPSILON);
 
-        let mut containers: Vec<Shape> = vec![];
-        for index in 0..xs.len() {
-            let i = xs[index];
-            if i == *self {
+        // Keyed by object id as well, so identical shapes placed as separate
+        // objects are still told apart.
+        let mut containers: Vec<(Option<ObjectId>, Shape)> = vec![];
+        for i in xs.iter() {
+            if *i == *self {
                 if containers.is_empty() {
                     comps.set_n1(1.0);
                 }else {
-                    comps.set_n1(containers.last().unwrap().material().refractive_index())
+                    comps.set_n1(containers.last().unwrap().1.material().refractive_index())
                 }
             }
 
-            if containers.contains(&i.object()) {
-                containers.remove(containers.iter().position(|x| *x == i.object()).unwrap());
+            let key = (i.object_id(), i.object());
+            if let Some(index) = containers.iter().position(|x| *x == key) {
+                containers.remove(index);
             }else {
-                containers.push(i.object());
+                containers.push(key);
             }
 
-            if i == *self {
+            if *i == *self {
                 if containers.is_empty() {
                     comps.set_n2(1.0);
                 } else {
-                    comps.set_n2(containers.last().unwrap().material().refractive_index());
+                    comps.set_n2(containers.last().unwrap().1.material().refractive_index());
                 }
 
                 break;
@@ -91,23 +119,20 @@ pub fn hit(xs: Vec<Intersection>) -> Option<Intersection> {
     let mut x: Vec<Intersection> = xs.clone();
     x.sort_by(|a, b| a.t().partial_cmp(&b.t()).unwrap());
 
-    if let Some(s) = x.iter().find(|f| f.t() >= 0.0) {
-        Some(*s)
-    } else {
-        None
-    }
+    x.iter().find(|f| f.t() >= 0.0).cloned()
 }
 
 impl PartialEq<Intersection> for Intersection {
     fn eq(&self, other: &Intersection) -> bool {
-        self.t == other.t && self.obj == other.obj
+        self.t == other.t && self.object_id == other.object_id && self.obj == other.obj
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct Computations {
     t: Num,
     object: Shape,
+    object_id: Option<ObjectId>,
     point: Tuple,
     eyev: Tuple,
     normalv: Tuple,
@@ -124,6 +149,7 @@ impl Computations {
         Computations {
             t: 0.0,
             object: Shape::sphere(),
+            object_id: None,
             point: Tuple::point(0, 0, 0),
             eyev: Tuple::vector(0, 0, 0),
             normalv: Tuple::vector(0, 0, 0),
@@ -154,14 +180,30 @@ impl Computations {
         let r0 = ((self.n1() - self.n2()) / (self.n1() + self.n2())).powi(2);
 
 
-        return r0 + (1.0 - r0) * (1.0 - cos).powi(5);
+        r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+    }
+
+    pub fn refracted_direction(&self) -> Option<Tuple> {
+        let n_ratio = self.n1() / self.n2();
+        let cos_i = self.eyev().dot(&self.normalv());
+        let sin2_t = n_ratio.powi(2) * (1.0 - cos_i.powi(2));
+
+        if sin2_t > 1.0 {
+            None
+        } else {
+            let cos_t = (1.0 - sin2_t).sqrt();
+            Some(self.normalv() * (n_ratio * cos_i - cos_t) - self.eyev() * n_ratio)
+        }
     }
 
     pub fn t(&self) -> Num {
         self.t
     }
     pub fn object(&self) -> Shape {
-        self.object
+        self.object.clone()
+    }
+    pub fn object_id(&self) -> Option<ObjectId> {
+        self.object_id
     }
     pub fn point(&self) -> Tuple {
         self.point
@@ -197,6 +239,9 @@ impl Computations {
     fn set_object(&mut self, val: Shape) {
         self.object = val;
     }
+    fn set_object_id(&mut self, val: Option<ObjectId>) {
+        self.object_id = val;
+    }
     fn set_point(&mut self, val: Tuple) {
         self.point = val;
     }
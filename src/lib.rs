@@ -1,11 +1,18 @@
+mod bvh;
 mod camera;
+mod integrator;
 mod intersection;
 mod light;
 mod material;
 mod matrix2x2;
 mod matrix3x3;
 mod matrix4x4;
+mod mesh;
+mod occlusion;
+mod photon_map;
 mod ray;
+mod roots;
+mod sampler;
 mod shape;
 mod transformation;
 mod tuple;
@@ -13,17 +20,20 @@ mod world;
 mod pattern;
 
 pub use crate::{
-    camera::Camera, img::*, intersection::*, light::Light, material::Material,
-    matrix2x2::Matrix2x2, matrix3x3::Matrix3x3, matrix4x4::Matrix4x4, ray::Ray, shape::Shape,
-    transformation::TransformationBuilder, tuple::Tuple, world::World, pattern::Pattern,
+    camera::Camera, img::*, integrator::{Integrator, PathTracer}, intersection::*, light::Light,
+    material::Material, occlusion::AmbientOcclusion,
+    photon_map::{Photon, PhotonMap},
+    matrix2x2::Matrix2x2, matrix3x3::Matrix3x3, matrix4x4::Matrix4x4, mesh::{ObjParser, PlyParser, StlParser},
+    ray::{Ray, RayKind},
+    shape::{Bounds, CsgOp, Falloff, Metaball, Quadric, Sdf, Shape, ShapeFlags, ShapeGeometry},
+    transformation::TransformationBuilder, tuple::Tuple, world::{ObjectId, World}, pattern::Pattern,
 };
 
 #[cfg(test)]
 mod tests;
 
 const EPSILON: Num = 0.0001;
-pub const PI: Num =
-    3.141592653589793238462643383279502884197169399375105820974944592307816406286208998628034825342;
+pub const PI: Num = std::f64::consts::PI;
 pub type Num = f64;
 
 fn equal(a: Num, b: Num) -> bool {
@@ -44,7 +54,7 @@ where
 }
 
 mod img {
-    use std::{ops::{Add, Mul}, fmt::Display};
+    use std::{fs, ops::{Add, Mul}, fmt::Display, path::Path};
 
     use crate::{equal, tuple::Tuple, Num};
 
@@ -67,6 +77,101 @@ mod img {
             }
             c
         }
+
+        // Reads a PPM or PGM image, plain (P3/P2) or raw (P6/P5), scaling
+        // samples to 0..1. Gray images fill all three channels.
+        pub fn from_ppm(bytes: &[u8]) -> Result<Canvas, String> {
+            let mut pos = 0;
+            let mut header = vec![];
+            while header.len() < 4 {
+                match next_token(bytes, &mut pos) {
+                    Some(token) => header.push(token),
+                    None => return Err("truncated header".to_string()),
+                }
+            }
+            let channels = match header[0].as_str() {
+                "P2" | "P5" => 1,
+                "P3" | "P6" => 3,
+                magic => return Err(format!("unsupported format '{}'", magic)),
+            };
+            let number = |token: &str, what: &str| {
+                token.parse::<u32>().map_err(|_| format!("invalid {} '{}'", what, token))
+            };
+            let width = number(&header[1], "width")?;
+            let height = number(&header[2], "height")?;
+            let max = number(&header[3], "maximum value")?;
+            if max == 0 || max > 65535 {
+                return Err(format!("invalid maximum value {}", max));
+            }
+
+            // Canvases index their pixels with u32, and the sample count has
+            // to fit before it's compared against the input.
+            let too_large = || format!("image of {}x{} is too large", width, height);
+            let count = width
+                .checked_mul(height)
+                .and_then(|pixels| (pixels as usize).checked_mul(channels))
+                .ok_or_else(too_large)?;
+            let samples: Vec<u32> = if header[0] == "P5" || header[0] == "P6" {
+                // A single whitespace byte separates the header from the data.
+                let width = if max > 255 { 2 } else { 1 };
+                let data = bytes.get(pos + 1..).unwrap_or(&[]);
+                let size = count.checked_mul(width).ok_or_else(too_large)?;
+                if data.len() < size {
+                    return Err(format!("expected {} bytes of pixel data, found {}", size, data.len()));
+                }
+                data.chunks(width).take(count).map(|c| c.iter().fold(0, |v, b| v << 8 | *b as u32)).collect()
+            } else {
+                let mut samples = vec![];
+                while samples.len() < count {
+                    match next_token(bytes, &mut pos) {
+                        Some(token) => samples.push(number(&token, "sample")?),
+                        None => return Err(format!("expected {} samples, found {}", count, samples.len())),
+                    }
+                }
+                samples
+            };
+
+            let mut canvas = Canvas::with_dimesnions(width, height);
+            let scale = |s: u32| s.min(max) as Num / max as Num;
+            for (i, pixel) in samples.chunks(channels).enumerate() {
+                let (x, y) = (i as u32 % width, i as u32 / width);
+                let color = match pixel {
+                    [g] => Pixel::rgb(scale(*g), scale(*g), scale(*g)),
+                    [r, g, b] => Pixel::rgb(scale(*r), scale(*g), scale(*b)),
+                    _ => unreachable!(),
+                };
+                canvas.set(x, y, color);
+            }
+            Ok(canvas)
+        }
+        pub fn from_ppm_file<P>(path: P) -> Result<Canvas, String>
+        where
+            P: AsRef<Path>,
+        {
+            let bytes = fs::read(path.as_ref()).map_err(|e| format!("{}: {}", path.as_ref().display(), e))?;
+            Canvas::from_ppm(&bytes)
+        }
+    }
+
+    // Next whitespace separated token of a PPM header or plain body,
+    // skipping `#` comments.
+    fn next_token(bytes: &[u8], pos: &mut usize) -> Option<String> {
+        loop {
+            while *pos < bytes.len() && bytes[*pos].is_ascii_whitespace() {
+                *pos += 1;
+            }
+            if bytes.get(*pos) != Some(&b'#') {
+                break;
+            }
+            while *pos < bytes.len() && bytes[*pos] != b'\n' {
+                *pos += 1;
+            }
+        }
+        let start = *pos;
+        while *pos < bytes.len() && !bytes[*pos].is_ascii_whitespace() {
+            *pos += 1;
+        }
+        (*pos > start).then(|| String::from_utf8_lossy(&bytes[start..*pos]).into_owned())
     }
 
     impl Canvas {
@@ -144,6 +249,16 @@ mod img {
             Pixel { r, g, b }
         }
 
+        pub fn r(&self) -> Num {
+            self.r
+        }
+        pub fn g(&self) -> Num {
+            self.g
+        }
+        pub fn b(&self) -> Num {
+            self.b
+        }
+
         pub fn to_tuple(&self) -> Tuple {
             Tuple::new(self.r, self.g, self.b, -1.0)
         }
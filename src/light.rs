@@ -1,31 +1,41 @@
-use crate::{img::Pixel, tuple::Tuple};
+use crate::{img::Pixel, ray::Ray, tuple::Tuple, Num, EPSILON};
 
 #[derive(Clone, Copy)]
 pub enum Light {
     Point(PointLight),
+    Area(AreaLight),
 }
 
 impl Light {
     pub fn position(&self) -> Tuple {
         match self {
             Light::Point(item) => item.position(),
+            Light::Area(item) => item.position(),
         }
     }
     pub fn intensity(&self) -> Pixel {
         match self {
             Light::Point(item) => item.intensity(),
+            Light::Area(item) => item.intensity(),
         }
     }
 
     pub fn point(position: Tuple, intensity: Pixel) -> Self {
         Light::Point(PointLight::new(intensity, position))
     }
+    // Parallelogram spanned by `uvec` and `vvec` from `corner`, emitting
+    // `intensity` as radiance from both faces.
+    pub fn area(corner: Tuple, uvec: Tuple, vvec: Tuple, intensity: Pixel) -> Self {
+        Light::Area(AreaLight::new(corner, uvec, vvec, intensity))
+    }
 }
 
 impl PartialEq<Light> for Light {
     fn eq(&self, other: &Light) -> bool {
         match (self, other) {
             (Self::Point(l0), Self::Point(r0)) => l0 == r0,
+            (Self::Area(l0), Self::Area(r0)) => l0 == r0,
+            _ => false,
         }
     }
 }
@@ -62,3 +72,76 @@ impl PartialEq<PointLight> for PointLight {
         self.intensity == other.intensity && self.position == other.position
     }
 }
+
+//AREALIGHT
+
+#[derive(Clone, Copy)]
+pub struct AreaLight {
+    corner: Tuple,
+    uvec: Tuple,
+    vvec: Tuple,
+    intensity: Pixel,
+}
+
+impl AreaLight {
+    fn new(corner: Tuple, uvec: Tuple, vvec: Tuple, intensity: Pixel) -> AreaLight {
+        AreaLight {
+            corner,
+            uvec,
+            vvec,
+            intensity,
+        }
+    }
+}
+
+impl AreaLight {
+    fn position(&self) -> Tuple {
+        self.point_at(0.5, 0.5)
+    }
+
+    fn intensity(&self) -> Pixel {
+        self.intensity
+    }
+
+    pub(crate) fn area(&self) -> Num {
+        self.uvec.cross(&self.vvec).magnitude()
+    }
+    pub(crate) fn normal(&self) -> Tuple {
+        self.uvec.cross(&self.vvec).normalize()
+    }
+    pub(crate) fn point_at(&self, u: Num, v: Num) -> Tuple {
+        self.corner + self.uvec * u + self.vvec * v
+    }
+
+    pub(crate) fn intersect(&self, ray: Ray) -> Option<Num> {
+        let n = self.normal();
+        let denom = n.dot(&ray.direction());
+        if denom.abs() < EPSILON {
+            return None;
+        }
+        let t = n.dot(&(self.corner - ray.origin())) / denom;
+        if t < EPSILON {
+            return None;
+        }
+
+        let rel = ray.position(t) - self.corner;
+        let u_dual = self.vvec.cross(&n);
+        let v_dual = n.cross(&self.uvec);
+        let u = rel.dot(&u_dual) / self.uvec.dot(&u_dual);
+        let v = rel.dot(&v_dual) / self.vvec.dot(&v_dual);
+        if (0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v) {
+            Some(t)
+        } else {
+            None
+        }
+    }
+}
+
+impl PartialEq<AreaLight> for AreaLight {
+    fn eq(&self, other: &AreaLight) -> bool {
+        self.corner == other.corner
+            && self.uvec == other.uvec
+            && self.vvec == other.vvec
+            && self.intensity == other.intensity
+    }
+}
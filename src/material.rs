@@ -39,7 +39,10 @@ impl Material {
             refractive_index: 1.0,
         }
     }
-    pub fn default() -> Material {
+}
+
+impl Default for Material {
+    fn default() -> Material {
         Material::new(Pixel::white(), 0.1, 0.9, 0.9, 200)
     }
 }
@@ -124,17 +127,44 @@ impl Material {
         normalv: Tuple,
         in_shadow: bool,
     ) -> Pixel {
-        let color = if let Some(pattern) = self.pattern() {
-            pattern.at_object(object, point)
-        }else{
-            self.color()
+        let transmission = if in_shadow {
+            Pixel::black()
+        } else {
+            Pixel::white()
         };
+        self.lighting_with_transmission(object, light, point, eyev, normalv, transmission)
+    }
+
+    pub fn lighting_with_transmission(
+        &self,
+        object: &Shape,
+        light: &Light,
+        point: Tuple,
+        eyev: Tuple,
+        normalv: Tuple,
+        transmission: Pixel,
+    ) -> Pixel {
+        self.lighting_occluded(object, light, point, eyev, normalv, transmission, 1.0)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn lighting_occluded(
+        &self,
+        object: &Shape,
+        light: &Light,
+        point: Tuple,
+        eyev: Tuple,
+        normalv: Tuple,
+        transmission: Pixel,
+        occlusion: Num,
+    ) -> Pixel {
+        let color = self.color_at(object, point);
 
         let effective_color = color * light.intensity();
         let lightv = (light.position() - point).normalize();
-        let ambient = effective_color * self.ambient;
+        let ambient = effective_color * (self.ambient * occlusion);
 
-        if in_shadow {
+        if transmission == Pixel::black() {
             return ambient;
         }
 
@@ -157,8 +187,17 @@ impl Material {
             (diffuse, specular)
         };
 
-        ambient + diffuse + specular
-            
+        ambient + (diffuse + specular) * transmission
+    }
+
+    pub fn color_at(&self, object: &Shape, point: Tuple) -> Pixel {
+        if let Some(pattern) = self.pattern() {
+            pattern.at_object(object, point)
+        } else if let Some(color) = object.vertex_color_at(point) {
+            color
+        } else {
+            self.color()
+        }
     }
 }
 
@@ -72,7 +72,7 @@ impl Matrix3x3 {
 
     pub fn cofactor(&self, row: usize, col: usize) -> Num {
         let m = self.minor(row, col);
-        if (row + col) % 2 == 0 {
+        if (row + col).is_multiple_of(2) {
             m
         } else {
             -m
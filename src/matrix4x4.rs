@@ -84,15 +84,22 @@ impl Matrix4x4 {
 
     pub fn cofactor(&self, row: usize, col: usize) -> Num {
         let m = self.minor(row, col);
-        if (row + col) % 2 == 0 {
+        if (row + col).is_multiple_of(2) {
             m
         } else {
             -m
         }
     }
 
+    // The determinant is compared against the product of the row lengths,
+    // its largest possible size, so small but regular matrices such as
+    // scaling(0.001, 0.001, 0.001) still count as invertible.
     pub fn invertible(&self) -> bool {
-        !equal(self.determinant(), 0.0)
+        let det = self.determinant();
+        let bound: Num = (0..4)
+            .map(|row| (0..4).map(|col| self.at(row, col).powi(2)).sum::<Num>().sqrt())
+            .product();
+        det.is_finite() && det.abs() > bound * Num::EPSILON
     }
 
     pub fn determinant(&self) -> Num {
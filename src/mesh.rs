@@ -0,0 +1,62 @@
+mod obj;
+mod ply;
+mod stl;
+pub use obj::ObjParser;
+pub use ply::PlyParser;
+pub use stl::StlParser;
+
+use crate::Num;
+
+// Parses every whitespace separated field as a number, naming the statement
+// in the error so messages read like "bad number 'x' in vertex".
+fn parse_numbers(fields: &[&str], what: &str) -> Result<Vec<Num>, String> {
+    fields
+        .iter()
+        .map(|f| {
+            f.parse::<Num>()
+                .map_err(|_| format!("bad number '{}' in {}", f, what))
+        })
+        .collect()
+}
+
+// Cursor over the body of a binary mesh file.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    little_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8], little_endian: bool) -> Reader<'a> {
+        Reader {
+            bytes,
+            pos: 0,
+            little_endian,
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos + len;
+        if end > self.bytes.len() {
+            return Err(format!("unexpected end of file at byte {}", self.bytes.len()));
+        }
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut array: [u8; N] = self.take(N)?.try_into().unwrap();
+        if self.little_endian != cfg!(target_endian = "little") {
+            array.reverse();
+        }
+        Ok(array)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        self.array().map(u32::from_ne_bytes)
+    }
+    fn f32(&mut self) -> Result<f32, String> {
+        self.array().map(f32::from_ne_bytes)
+    }
+}
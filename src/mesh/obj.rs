@@ -0,0 +1,199 @@
+use std::{fs, path::Path};
+
+use super::parse_numbers;
+use crate::{Num, Shape, Tuple};
+
+// Reads the geometry out of a Wavefront OBJ file. Faces with more than three
+// vertices are split into a fan of triangles, and faces whose vertices all
+// carry normals become smooth triangles.
+pub struct ObjParser {
+    vertices: Vec<Tuple>,
+    normals: Vec<Tuple>,
+    texture_coords: Vec<(Num, Num)>,
+    default_group: Vec<Shape>,
+    groups: Vec<(String, Vec<Shape>)>,
+    skipped_lines: usize,
+}
+
+// One corner of a face, as zero based indices into the parsed lists.
+struct FaceVertex {
+    vertex: usize,
+    normal: Option<usize>,
+}
+
+impl ObjParser {
+    pub fn parse(source: &str) -> Result<ObjParser, String> {
+        let mut parser = ObjParser {
+            vertices: vec![],
+            normals: vec![],
+            texture_coords: vec![],
+            default_group: vec![],
+            groups: vec![],
+            skipped_lines: 0,
+        };
+        let mut current: Option<usize> = None;
+
+        for (n, line) in source.lines().enumerate() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let result = match fields.first() {
+                None => Ok(()),
+                Some(f) if f.starts_with('#') => Ok(()),
+                Some(&"v") => parser.parse_vertex(&fields[1..]),
+                Some(&"vn") => parser.parse_normal(&fields[1..]),
+                Some(&"vt") => parser.parse_texture_coord(&fields[1..]),
+                Some(&"f") => parser.parse_face(&fields[1..]).map(|triangles| match current {
+                    Some(g) => parser.groups[g].1.extend(triangles),
+                    None => parser.default_group.extend(triangles),
+                }),
+                Some(&"g") => {
+                    let name = fields[1..].join(" ");
+                    current = match parser.groups.iter().position(|(g, _)| *g == name) {
+                        Some(g) => Some(g),
+                        None => {
+                            parser.groups.push((name, vec![]));
+                            Some(parser.groups.len() - 1)
+                        }
+                    };
+                    Ok(())
+                }
+                Some(_) => {
+                    parser.skipped_lines += 1;
+                    Ok(())
+                }
+            };
+            result.map_err(|e| format!("line {}: {}", n + 1, e))?;
+        }
+
+        Ok(parser)
+    }
+
+    pub fn from_file<P>(path: P) -> Result<ObjParser, String>
+    where
+        P: AsRef<Path>,
+    {
+        let source = fs::read_to_string(path.as_ref())
+            .map_err(|e| format!("{}: {}", path.as_ref().display(), e))?;
+        ObjParser::parse(&source)
+    }
+
+    fn parse_vertex(&mut self, fields: &[&str]) -> Result<(), String> {
+        let nums = parse_numbers(fields, "vertex")?;
+        if nums.len() != 3 && nums.len() != 4 {
+            return Err(format!("vertex needs 3 coordinates, got {}", nums.len()));
+        }
+        self.vertices.push(Tuple::point(nums[0], nums[1], nums[2]));
+        Ok(())
+    }
+
+    fn parse_normal(&mut self, fields: &[&str]) -> Result<(), String> {
+        let nums = parse_numbers(fields, "normal")?;
+        if nums.len() != 3 {
+            return Err(format!("normal needs 3 coordinates, got {}", nums.len()));
+        }
+        self.normals.push(Tuple::vector(nums[0], nums[1], nums[2]));
+        Ok(())
+    }
+
+    fn parse_texture_coord(&mut self, fields: &[&str]) -> Result<(), String> {
+        let nums = parse_numbers(fields, "texture coordinate")?;
+        if nums.is_empty() || nums.len() > 3 {
+            return Err(format!("texture coordinate needs 1 to 3 values, got {}", nums.len()));
+        }
+        self.texture_coords.push((nums[0], nums.get(1).copied().unwrap_or(0.0)));
+        Ok(())
+    }
+
+    fn parse_face(&self, fields: &[&str]) -> Result<Vec<Shape>, String> {
+        if fields.len() < 3 {
+            return Err(format!("face needs at least 3 vertices, got {}", fields.len()));
+        }
+        let corners = fields
+            .iter()
+            .map(|f| self.parse_face_vertex(f))
+            .collect::<Result<Vec<FaceVertex>, String>>()?;
+        let smooth = corners.iter().all(|c| c.normal.is_some());
+
+        let mut triangles = vec![];
+        for i in 1..corners.len() - 1 {
+            let (a, b, c) = (&corners[0], &corners[i], &corners[i + 1]);
+            let (p1, p2, p3) = (self.vertices[a.vertex], self.vertices[b.vertex], self.vertices[c.vertex]);
+            triangles.push(if smooth {
+                Shape::smooth_triangle(
+                    p1,
+                    p2,
+                    p3,
+                    self.normals[a.normal.unwrap()],
+                    self.normals[b.normal.unwrap()],
+                    self.normals[c.normal.unwrap()],
+                )
+            } else {
+                Shape::triangle(p1, p2, p3)
+            });
+        }
+        Ok(triangles)
+    }
+
+    // Accepts "v", "v/vt", "v//vn" and "v/vt/vn".
+    fn parse_face_vertex(&self, field: &str) -> Result<FaceVertex, String> {
+        let parts: Vec<&str> = field.split('/').collect();
+        if parts.len() > 3 {
+            return Err(format!("bad face vertex '{}'", field));
+        }
+        let vertex = resolve_index(parts[0], self.vertices.len(), "vertex")?;
+        if let Some(vt) = parts.get(1).filter(|p| !p.is_empty()) {
+            resolve_index(vt, self.texture_coords.len(), "texture coordinate")?;
+        }
+        let normal = match parts.get(2).filter(|p| !p.is_empty()) {
+            Some(vn) => Some(resolve_index(vn, self.normals.len(), "normal")?),
+            None => None,
+        };
+        Ok(FaceVertex { vertex, normal })
+    }
+}
+
+impl ObjParser {
+    pub fn vertices(&self) -> &[Tuple] {
+        &self.vertices
+    }
+    pub fn normals(&self) -> &[Tuple] {
+        &self.normals
+    }
+    pub fn texture_coords(&self) -> &[(Num, Num)] {
+        &self.texture_coords
+    }
+    pub fn skipped_lines(&self) -> usize {
+        self.skipped_lines
+    }
+    pub fn default_group(&self) -> &[Shape] {
+        &self.default_group
+    }
+    pub fn group_names(&self) -> Vec<&str> {
+        self.groups.iter().map(|(name, _)| name.as_str()).collect()
+    }
+    pub fn group(&self, name: &str) -> Option<&[Shape]> {
+        self.groups
+            .iter()
+            .find(|(g, _)| g == name)
+            .map(|(_, shapes)| shapes.as_slice())
+    }
+
+    // Everything in one group shape, with each named group as a child group.
+    pub fn to_group(&self) -> Shape {
+        let mut children = self.default_group.clone();
+        children.extend(self.groups.iter().map(|(_, shapes)| Shape::group_of(shapes.clone())));
+        Shape::group_of(children)
+    }
+}
+
+// OBJ indices start at 1, and negative ones count back from the latest
+// element read so far.
+fn resolve_index(field: &str, len: usize, what: &str) -> Result<usize, String> {
+    let index: i64 = field
+        .parse()
+        .map_err(|_| format!("bad {} index '{}'", what, field))?;
+    let resolved = if index < 0 { len as i64 + index } else { index - 1 };
+    if index == 0 || resolved < 0 || resolved >= len as i64 {
+        return Err(format!("{} index {} out of range", what, index));
+    }
+    Ok(resolved as usize)
+}
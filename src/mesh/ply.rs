@@ -0,0 +1,377 @@
+use std::{fs, path::Path};
+
+use super::Reader;
+use crate::{Num, Pixel, Shape, Tuple};
+
+// Reads ASCII and binary PLY. Vertices give x/y/z and, when present, nx/ny/nz
+// normals (making smooth triangles) and red/green/blue colors (becoming
+// vertex colors). Faces are split into fans; other elements are skipped.
+pub struct PlyParser {
+    vertices: Vec<Tuple>,
+    normals: Vec<Tuple>,
+    colors: Vec<Pixel>,
+    faces: Vec<Vec<usize>>,
+    triangles: Vec<Shape>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Scalar> {
+        match name {
+            "char" | "int8" => Some(Scalar::I8),
+            "uchar" | "uint8" => Some(Scalar::U8),
+            "short" | "int16" => Some(Scalar::I16),
+            "ushort" | "uint16" => Some(Scalar::U16),
+            "int" | "int32" => Some(Scalar::I32),
+            "uint" | "uint32" => Some(Scalar::U32),
+            "float" | "float32" => Some(Scalar::F32),
+            "double" | "float64" => Some(Scalar::F64),
+            _ => None,
+        }
+    }
+
+    // Integer colors span their whole range, float ones are already 0..1.
+    fn color_scale(&self) -> Num {
+        match self {
+            Scalar::U8 | Scalar::I8 => 255.0,
+            Scalar::U16 | Scalar::I16 => 65535.0,
+            Scalar::I32 | Scalar::U32 => 4294967295.0,
+            Scalar::F32 | Scalar::F64 => 1.0,
+        }
+    }
+}
+
+struct Property {
+    name: String,
+    kind: Scalar,
+    // Type of the length prefix for list properties.
+    count: Option<Scalar>,
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    fn property(&self, name: &str) -> Option<usize> {
+        self.properties.iter().position(|p| p.name == name)
+    }
+}
+
+#[derive(PartialEq)]
+enum Format {
+    Ascii,
+    Binary { little_endian: bool },
+}
+
+impl Reader<'_> {
+    fn scalar(&mut self, kind: Scalar) -> Result<Num, String> {
+        Ok(match kind {
+            Scalar::I8 => i8::from_ne_bytes(self.array()?) as Num,
+            Scalar::U8 => u8::from_ne_bytes(self.array()?) as Num,
+            Scalar::I16 => i16::from_ne_bytes(self.array()?) as Num,
+            Scalar::U16 => u16::from_ne_bytes(self.array()?) as Num,
+            Scalar::I32 => i32::from_ne_bytes(self.array()?) as Num,
+            Scalar::U32 => u32::from_ne_bytes(self.array()?) as Num,
+            Scalar::F32 => f32::from_ne_bytes(self.array()?) as Num,
+            Scalar::F64 => f64::from_ne_bytes(self.array()?),
+        })
+    }
+}
+
+impl PlyParser {
+    pub fn parse(bytes: &[u8]) -> Result<PlyParser, String> {
+        let header_end = bytes
+            .windows(10)
+            .position(|w| w == b"end_header")
+            .ok_or("missing end_header")?;
+        let body_start = match bytes[header_end + 10..].iter().position(|&b| b == b'\n') {
+            Some(offset) => header_end + 10 + offset + 1,
+            None => bytes.len(),
+        };
+        let header = std::str::from_utf8(&bytes[..header_end]).map_err(|e| format!("header is not text: {}", e))?;
+        let (format, elements) = parse_header(header)?;
+
+        let values = match format {
+            Format::Ascii => {
+                let body = std::str::from_utf8(&bytes[body_start..]).map_err(|e| format!("ASCII body is not text: {}", e))?;
+                read_ascii(body, header.lines().count() + 1, &elements)?
+            }
+            Format::Binary { little_endian } => {
+                read_binary(&mut Reader::new(&bytes[body_start..], little_endian), &elements)?
+            }
+        };
+
+        let mut parser = PlyParser {
+            vertices: vec![],
+            normals: vec![],
+            colors: vec![],
+            faces: vec![],
+            triangles: vec![],
+        };
+        for (element, rows) in elements.iter().zip(values) {
+            match element.name.as_str() {
+                "vertex" => parser.read_vertices(element, rows)?,
+                "face" => parser.read_faces(element, rows)?,
+                _ => {}
+            }
+        }
+        parser.build_triangles()?;
+        Ok(parser)
+    }
+
+    pub fn from_file<P>(path: P) -> Result<PlyParser, String>
+    where
+        P: AsRef<Path>,
+    {
+        let bytes = fs::read(path.as_ref()).map_err(|e| format!("{}: {}", path.as_ref().display(), e))?;
+        PlyParser::parse(&bytes)
+    }
+
+    fn read_vertices(&mut self, element: &Element, rows: Vec<Vec<Vec<Num>>>) -> Result<(), String> {
+        let find = |names: [&str; 3]| -> Option<[usize; 3]> {
+            Some([element.property(names[0])?, element.property(names[1])?, element.property(names[2])?])
+        };
+        let position = find(["x", "y", "z"]).ok_or("vertex element needs x, y and z")?;
+        let normal = find(["nx", "ny", "nz"]);
+        let color = find(["red", "green", "blue"]);
+        let scale = color.map(|[r, g, b]| {
+            [r, g, b].map(|i| element.properties[i].kind.color_scale())
+        });
+
+        for row in rows {
+            let at = |i: usize| row[i][0];
+            self.vertices.push(Tuple::point(at(position[0]), at(position[1]), at(position[2])));
+            if let Some([x, y, z]) = normal {
+                self.normals.push(Tuple::vector(at(x), at(y), at(z)));
+            }
+            if let (Some([r, g, b]), Some(s)) = (color, scale) {
+                self.colors.push(Pixel::rgb(at(r) / s[0], at(g) / s[1], at(b) / s[2]));
+            }
+        }
+        Ok(())
+    }
+
+    fn read_faces(&mut self, element: &Element, rows: Vec<Vec<Vec<Num>>>) -> Result<(), String> {
+        let indices = element
+            .property("vertex_indices")
+            .or_else(|| element.property("vertex_index"))
+            .ok_or("face element needs vertex_indices")?;
+        for (n, row) in rows.iter().enumerate() {
+            let face = row[indices].iter().map(|&i| whole_number(i)).collect::<Result<_, _>>();
+            self.faces.push(face.map_err(|e| format!("face {}: {}", n, e))?);
+        }
+        Ok(())
+    }
+
+    fn build_triangles(&mut self) -> Result<(), String> {
+        for (n, face) in self.faces.iter().enumerate() {
+            if face.len() < 3 {
+                return Err(format!("face {}: needs at least 3 vertices, got {}", n, face.len()));
+            }
+            if let Some(i) = face.iter().find(|&&i| i >= self.vertices.len()) {
+                return Err(format!("face {}: vertex index {} out of range", n, i));
+            }
+            for i in 1..face.len() - 1 {
+                let [a, b, c] = [face[0], face[i], face[i + 1]];
+                let (p1, p2, p3) = (self.vertices[a], self.vertices[b], self.vertices[c]);
+                let mut triangle = if self.normals.is_empty() {
+                    Shape::triangle(p1, p2, p3)
+                } else {
+                    Shape::smooth_triangle(p1, p2, p3, self.normals[a], self.normals[b], self.normals[c])
+                };
+                if !self.colors.is_empty() {
+                    triangle.set_vertex_colors(self.colors[a], self.colors[b], self.colors[c])?;
+                }
+                self.triangles.push(triangle);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PlyParser {
+    pub fn vertices(&self) -> &[Tuple] {
+        &self.vertices
+    }
+    pub fn normals(&self) -> &[Tuple] {
+        &self.normals
+    }
+    pub fn colors(&self) -> &[Pixel] {
+        &self.colors
+    }
+    pub fn faces(&self) -> &[Vec<usize>] {
+        &self.faces
+    }
+    pub fn triangles(&self) -> &[Shape] {
+        &self.triangles
+    }
+    pub fn to_group(&self) -> Shape {
+        Shape::group_of(self.triangles.clone())
+    }
+}
+
+fn parse_header(header: &str) -> Result<(Format, Vec<Element>), String> {
+    let mut format = None;
+    let mut elements: Vec<Element> = vec![];
+
+    for (n, line) in header.lines().enumerate() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let result = match fields.as_slice() {
+            [] | ["comment", ..] | ["obj_info", ..] => Ok(()),
+            ["ply"] if n == 0 => Ok(()),
+            _ if n == 0 => Err("not a PLY file".to_string()),
+            ["format", kind, "1.0"] => {
+                format = match *kind {
+                    "ascii" => Some(Format::Ascii),
+                    "binary_little_endian" => Some(Format::Binary { little_endian: true }),
+                    "binary_big_endian" => Some(Format::Binary { little_endian: false }),
+                    _ => None,
+                };
+                format.as_ref().map(|_| ()).ok_or(format!("unknown format '{}'", kind))
+            }
+            ["element", name, count] => match count.parse() {
+                Ok(count) => {
+                    elements.push(Element {
+                        name: name.to_string(),
+                        count,
+                        properties: vec![],
+                    });
+                    Ok(())
+                }
+                Err(_) => Err(format!("bad element count '{}'", count)),
+            },
+            ["property", "list", count, kind, name] => match (elements.last_mut(), Scalar::parse(count), Scalar::parse(kind)) {
+                (None, _, _) => Err("property before any element".to_string()),
+                (Some(element), Some(count), Some(kind)) => {
+                    element.properties.push(Property {
+                        name: name.to_string(),
+                        kind,
+                        count: Some(count),
+                    });
+                    Ok(())
+                }
+                _ => Err(format!("unknown list types '{} {}'", count, kind)),
+            },
+            ["property", kind, name] => match (elements.last_mut(), Scalar::parse(kind)) {
+                (None, _) => Err("property before any element".to_string()),
+                (Some(element), Some(kind)) => {
+                    element.properties.push(Property {
+                        name: name.to_string(),
+                        kind,
+                        count: None,
+                    });
+                    Ok(())
+                }
+                _ => Err(format!("unknown property type '{}'", kind)),
+            },
+            _ => Err(format!("bad header line '{}'", line.trim())),
+        };
+        result.map_err(|e| format!("line {}: {}", n + 1, e))?;
+    }
+
+    if let Some(element) = elements.iter().find(|e| e.properties.is_empty() && e.count > 0) {
+        return Err(format!("element '{}' has no properties", element.name));
+    }
+
+    // Vertices read each of these as a single value.
+    for element in elements.iter().filter(|e| e.name == "vertex") {
+        for name in ["x", "y", "z", "nx", "ny", "nz", "red", "green", "blue"] {
+            if element.property(name).is_some_and(|i| element.properties[i].count.is_some()) {
+                return Err(format!("vertex property '{}' cannot be a list", name));
+            }
+        }
+    }
+
+    Ok((format.ok_or("missing format line")?, elements))
+}
+
+// Face indices and list lengths are read like any other value, so they have
+// to be checked before use as a usize.
+fn whole_number(value: Num) -> Result<usize, String> {
+    if value >= 0.0 && value.fract() == 0.0 && value <= usize::MAX as Num {
+        Ok(value as usize)
+    } else {
+        Err(format!("expected a non-negative integer, got {}", value))
+    }
+}
+
+// Values of every property of every row of every element, with scalars as
+// one element lists.
+type ElementValues = Vec<Vec<Vec<Num>>>;
+
+fn read_ascii(body: &str, first_line: usize, elements: &[Element]) -> Result<Vec<ElementValues>, String> {
+    let mut lines = body.lines().enumerate().filter(|(_, l)| !l.trim().is_empty());
+    let mut values = vec![];
+    for element in elements {
+        let mut rows = vec![];
+        for _ in 0..element.count {
+            let (n, line) = lines
+                .next()
+                .ok_or(format!("unexpected end of file in element '{}'", element.name))?;
+            let line_error = |e: String| format!("line {}: {}", first_line + n + 1, e);
+            let mut fields = line.split_whitespace();
+            let mut next = || -> Result<Num, String> {
+                let field = fields.next().ok_or("too few values")?;
+                field.parse().map_err(|_| format!("bad number '{}'", field))
+            };
+
+            let mut row = vec![];
+            for property in &element.properties {
+                let len = match property.count {
+                    Some(_) => next().and_then(whole_number).map_err(line_error)?,
+                    None => 1,
+                };
+                row.push((0..len).map(|_| next()).collect::<Result<Vec<Num>, String>>().map_err(line_error)?);
+            }
+            if fields.next().is_some() {
+                return Err(line_error("too many values".to_string()));
+            }
+            rows.push(row);
+        }
+        values.push(rows);
+    }
+    Ok(values)
+}
+
+fn read_binary(reader: &mut Reader, elements: &[Element]) -> Result<Vec<ElementValues>, String> {
+    let mut values = vec![];
+    for element in elements {
+        let mut rows = vec![];
+        for i in 0..element.count {
+            let start = reader.pos;
+            let mut row = vec![];
+            for property in &element.properties {
+                let mut read = || -> Result<Vec<Num>, String> {
+                    let len = match property.count {
+                        Some(count) => whole_number(reader.scalar(count)?)?,
+                        None => 1,
+                    };
+                    (0..len).map(|_| reader.scalar(property.kind)).collect()
+                };
+                row.push(read().map_err(|e| format!("{} {}: {}", element.name, i, e))?);
+            }
+            // Every row has to use up some of the file, or a header alone
+            // could ask for any number of them.
+            if reader.pos == start {
+                return Err(format!("{} {}: row holds no data", element.name, i));
+            }
+            rows.push(row);
+        }
+        values.push(rows);
+    }
+    Ok(values)
+}
@@ -0,0 +1,145 @@
+use std::{fs, path::Path};
+
+use super::{parse_numbers, Reader};
+use crate::{Shape, Tuple};
+
+// Reads ASCII or binary STL. Facet normals in the file are ignored in favour
+// of the winding of the vertices, which is what every exporter keeps right.
+pub struct StlParser {
+    name: String,
+    triangles: Vec<Shape>,
+}
+
+impl StlParser {
+    pub fn parse(bytes: &[u8]) -> Result<StlParser, String> {
+        // Binary files may also start with "solid", so their exact size and
+        // being text at all decide.
+        let binary_size = bytes
+            .get(80..84)
+            .and_then(|count| binary_size(u32::from_le_bytes(count.try_into().unwrap())));
+        match std::str::from_utf8(bytes) {
+            Ok(source) if source.starts_with("solid") && binary_size != Some(bytes.len()) => {
+                StlParser::parse_ascii(source)
+            }
+            _ => StlParser::parse_binary(bytes),
+        }
+    }
+
+    pub fn from_file<P>(path: P) -> Result<StlParser, String>
+    where
+        P: AsRef<Path>,
+    {
+        let bytes = fs::read(path.as_ref()).map_err(|e| format!("{}: {}", path.as_ref().display(), e))?;
+        StlParser::parse(&bytes)
+    }
+
+    fn parse_binary(bytes: &[u8]) -> Result<StlParser, String> {
+        let mut reader = Reader::new(bytes, true);
+        let header = reader.take(80)?;
+        let name = String::from_utf8_lossy(header).trim_end_matches('\0').trim().to_string();
+        let count = reader.u32()?;
+        // The count comes straight from the file, so it has to agree with
+        // the data actually present before anything is allocated for it.
+        match binary_size(count) {
+            Some(size) if size == bytes.len() => {}
+            _ => {
+                return Err(format!(
+                    "header declares {} triangles but the file is {} bytes long",
+                    count,
+                    bytes.len()
+                ))
+            }
+        }
+
+        let mut triangles = Vec::with_capacity(count as usize);
+        for i in 0..count {
+            let mut floats = [0.0; 12];
+            for f in floats.iter_mut() {
+                *f = reader
+                    .f32()
+                    .map_err(|e| format!("triangle {}: {}", i + 1, e))?
+                    .into();
+            }
+            reader.take(2).map_err(|e| format!("triangle {}: {}", i + 1, e))?;
+            triangles.push(Shape::triangle(
+                Tuple::point(floats[3], floats[4], floats[5]),
+                Tuple::point(floats[6], floats[7], floats[8]),
+                Tuple::point(floats[9], floats[10], floats[11]),
+            ));
+        }
+
+        Ok(StlParser { name, triangles })
+    }
+
+    fn parse_ascii(source: &str) -> Result<StlParser, String> {
+        let mut name = String::new();
+        let mut triangles = vec![];
+        let mut corners: Option<Vec<Tuple>> = None;
+
+        for (n, line) in source.lines().enumerate() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let result = match fields.first() {
+                None => Ok(()),
+                Some(&"solid") => {
+                    name = fields[1..].join(" ");
+                    Ok(())
+                }
+                Some(&"facet") => match fields.get(1) {
+                    Some(&"normal") => parse_numbers(&fields[2..], "facet normal").and_then(|nums| {
+                        if nums.len() == 3 {
+                            Ok(())
+                        } else {
+                            Err(format!("facet normal needs 3 values, got {}", nums.len()))
+                        }
+                    }),
+                    _ => Err("expected 'facet normal'".to_string()),
+                },
+                Some(&"outer") => {
+                    corners = Some(vec![]);
+                    Ok(())
+                }
+                Some(&"vertex") => match corners.as_mut() {
+                    Some(corners) => parse_numbers(&fields[1..], "vertex").and_then(|nums| {
+                        if nums.len() == 3 {
+                            corners.push(Tuple::point(nums[0], nums[1], nums[2]));
+                            Ok(())
+                        } else {
+                            Err(format!("vertex needs 3 coordinates, got {}", nums.len()))
+                        }
+                    }),
+                    None => Err("vertex outside of a loop".to_string()),
+                },
+                Some(&"endloop") => match corners.take() {
+                    Some(c) if c.len() == 3 => {
+                        triangles.push(Shape::triangle(c[0], c[1], c[2]));
+                        Ok(())
+                    }
+                    Some(c) => Err(format!("facet needs 3 vertices, got {}", c.len())),
+                    None => Err("endloop without a loop".to_string()),
+                },
+                Some(&"endfacet") | Some(&"endsolid") => Ok(()),
+                Some(other) => Err(format!("unknown statement '{}'", other)),
+            };
+            result.map_err(|e| format!("line {}: {}", n + 1, e))?;
+        }
+
+        Ok(StlParser { name, triangles })
+    }
+}
+
+impl StlParser {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn triangles(&self) -> &[Shape] {
+        &self.triangles
+    }
+    pub fn to_group(&self) -> Shape {
+        Shape::group_of(self.triangles.clone())
+    }
+}
+
+// Total size of a binary STL holding `count` triangles, if it fits in memory.
+fn binary_size(count: u32) -> Option<usize> {
+    usize::try_from(count).ok()?.checked_mul(50)?.checked_add(84)
+}
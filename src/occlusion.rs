@@ -0,0 +1,54 @@
+use crate::{
+    hit,
+    ray::{Ray, RayKind},
+    sampler::{self, Sampler},
+    tuple::Tuple,
+    world::World,
+    Num,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct AmbientOcclusion {
+    samples: u32,
+    max_distance: Num,
+}
+
+impl AmbientOcclusion {
+    pub fn new<T>(samples: u32, max_distance: T) -> AmbientOcclusion
+    where
+        T: Into<Num>,
+    {
+        AmbientOcclusion {
+            samples: samples.max(1),
+            max_distance: max_distance.into(),
+        }
+    }
+}
+
+impl AmbientOcclusion {
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+    pub fn max_distance(&self) -> Num {
+        self.max_distance
+    }
+}
+
+impl World {
+    // Fraction of the cosine weighted hemisphere around `normal` that is not
+    // blocked within the maximum distance: 1 is fully open, 0 fully occluded.
+    pub fn ambient_occlusion_at(&self, point: Tuple, normal: Tuple, ao: AmbientOcclusion) -> Num {
+        let mut sampler = Sampler::for_point(point);
+        let mut open = 0;
+        for _ in 0..ao.samples() {
+            let direction = sampler::cosine_hemisphere(normal, &mut sampler);
+            let r = Ray::new(point, direction);
+            match hit(self.intersect_world_for(r, RayKind::Shadow)) {
+                Some(h) if h.t() < ao.max_distance() => {}
+                _ => open += 1,
+            }
+        }
+
+        open as Num / ao.samples() as Num
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Pixel, Tuple, Matrix4x4, Shape};
+use crate::{Pixel, Tuple, Matrix4x4, Shape, transformation::Transform};
 
 mod stripe_pattern;
 mod gradient_pattern;
@@ -28,13 +28,16 @@ impl Pattern {
         }
     }
     pub fn at_object(&self, object: &Shape, world_point: Tuple) -> Pixel{
-        let object_point = object.transform().inverse().unwrap() * world_point;
-        let pattern_point = self.transform().inverse().unwrap() * object_point;
+        let object_point = object.world_to_object(world_point);
+        let pattern_point = self.cached_transform().inverse() * object_point;
 
         self.at(pattern_point)
     }
 
     pub fn transform(&self) -> Matrix4x4{
+        self.cached_transform().matrix()
+    }
+    fn cached_transform(&self) -> &Transform{
         match self {
             Self::Stripe(item) => item.transform(),
             Self::Gradient(item) => item.transform(),
@@ -43,7 +46,9 @@ impl Pattern {
             Self::Test(item) => item.transform(),
         }
     }
-    pub fn set_transform(&mut self, transform: Matrix4x4){
+    // Fails, leaving the pattern as it was, if the matrix can't be inverted.
+    pub fn set_transform(&mut self, transform: Matrix4x4) -> Result<(), String>{
+        let transform = Transform::new(transform)?;
         match self {
             Self::Stripe(item) => item.set_transform(transform),
             Self::Gradient(item) => item.set_transform(transform),
@@ -51,6 +56,7 @@ impl Pattern {
             Self::Checkers(item) => item.set_transform(transform),
             Self::Test(item) => item.set_transform(transform),
         }
+        Ok(())
     }
 }
 
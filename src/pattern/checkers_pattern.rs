@@ -1,10 +1,10 @@
-use crate::{Pixel, Tuple, equal, Pattern, Matrix4x4};
+use crate::{Pixel, Tuple, equal, Pattern, transformation::Transform};
 
 #[derive(Clone, Copy)]
 pub struct CheckersPattern{
     color_a: Pixel,
     color_b: Pixel,
-    transform: Matrix4x4,
+    transform: Transform,
 }
 
 impl CheckersPattern {
@@ -16,17 +16,17 @@ impl CheckersPattern {
         }
     }
     
-    pub(super) fn transform(&self) -> Matrix4x4{
-        self.transform
+    pub(super) fn transform(&self) -> &Transform{
+        &self.transform
     }
-    pub(super) fn set_transform(&mut self, transform: Matrix4x4){
+    pub(super) fn set_transform(&mut self, transform: Transform){
         self.transform = transform;
     }
 }
 
 impl Pattern {
     pub fn checkers(color_a: Pixel, color_b: Pixel) -> Pattern{
-        Pattern::Checkers(CheckersPattern { color_a, color_b, transform: Matrix4x4::identity() })
+        Pattern::Checkers(CheckersPattern { color_a, color_b, transform: Transform::identity() })
     }
 }
 
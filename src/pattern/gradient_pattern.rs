@@ -1,10 +1,10 @@
-use crate::{Pixel, Tuple, Pattern, Matrix4x4};
+use crate::{Pixel, Tuple, Pattern, transformation::Transform};
 
 #[derive(Clone, Copy)]
 pub struct GradientPattern{
     color_a: Pixel,
     color_b: Pixel,
-    transform: Matrix4x4,
+    transform: Transform,
 }
 
 impl GradientPattern {
@@ -16,17 +16,17 @@ impl GradientPattern {
         self.color_a + Pixel::rgb(a.get_x(), a.get_y(), a.get_z())
     }
     
-    pub(super) fn transform(&self) -> Matrix4x4{
-        self.transform
+    pub(super) fn transform(&self) -> &Transform{
+        &self.transform
     }
-    pub(super) fn set_transform(&mut self, transform: Matrix4x4){
+    pub(super) fn set_transform(&mut self, transform: Transform){
         self.transform = transform;
     }
 }
 
 impl Pattern {
     pub fn gradient(color_a: Pixel, color_b: Pixel) -> Pattern{
-        Pattern::Gradient(GradientPattern { color_a, color_b, transform: Matrix4x4::identity() })
+        Pattern::Gradient(GradientPattern { color_a, color_b, transform: Transform::identity() })
     }
 }
 
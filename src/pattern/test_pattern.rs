@@ -1,8 +1,8 @@
-use crate::{Pixel, Tuple, Pattern, Matrix4x4};
+use crate::{Pixel, Tuple, Pattern, transformation::Transform};
 
 #[derive(Clone, Copy)]
 pub struct TestPattern{
-    transform: Matrix4x4,
+    transform: Transform,
 }
 
 impl TestPattern {
@@ -10,17 +10,18 @@ impl TestPattern {
         Pixel::rgb(point.get_x(), point.get_y(), point.get_z())
     }
     
-    pub(super) fn transform(&self) -> Matrix4x4{
-        self.transform
+    pub(super) fn transform(&self) -> &Transform{
+        &self.transform
     }
-    pub(super) fn set_transform(&mut self, transform: Matrix4x4){
+    pub(super) fn set_transform(&mut self, transform: Transform){
         self.transform = transform;
     }
 }
 
 impl Pattern {
+    #[cfg(test)]
     pub(crate) fn test() -> Pattern{
-        Pattern::Test(TestPattern { transform: Matrix4x4::identity() })
+        Pattern::Test(TestPattern { transform: Transform::identity() })
     }
 }
 
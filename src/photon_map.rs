@@ -0,0 +1,279 @@
+use crate::{
+    hit,
+    img::Pixel,
+    ray::{Ray, RayKind},
+    sampler::{self, Sampler},
+    tuple::Tuple,
+    world::World,
+    Num, PI,
+};
+
+const MAX_PHOTON_BOUNCES: u32 = 8;
+
+#[derive(Clone, Copy)]
+pub struct Photon {
+    position: Tuple,
+    direction: Tuple,
+    power: Pixel,
+}
+
+impl Photon {
+    pub fn new(position: Tuple, direction: Tuple, power: Pixel) -> Photon {
+        Photon {
+            position,
+            direction,
+            power,
+        }
+    }
+}
+
+impl Photon {
+    pub fn position(&self) -> Tuple {
+        self.position
+    }
+    pub fn direction(&self) -> Tuple {
+        self.direction
+    }
+    pub fn power(&self) -> Pixel {
+        self.power
+    }
+
+    fn axis(&self, axis: usize) -> Num {
+        match axis {
+            0 => self.position.get_x(),
+            1 => self.position.get_y(),
+            _ => self.position.get_z(),
+        }
+    }
+}
+
+// Photons stored as an implicit balanced kd-tree: the median of every range
+// is its node and the halves on either side are its subtrees.
+#[derive(Clone)]
+pub struct PhotonMap {
+    photons: Vec<Photon>,
+    axes: Vec<usize>,
+    gather_count: usize,
+    gather_radius: Num,
+}
+
+impl PhotonMap {
+    pub fn new(photons: Vec<Photon>) -> PhotonMap {
+        let mut map = PhotonMap {
+            axes: vec![0; photons.len()],
+            photons,
+            gather_count: 50,
+            gather_radius: 0.5,
+        };
+        let len = map.photons.len();
+        map.build(0, len);
+        map
+    }
+
+    fn build(&mut self, start: usize, end: usize) {
+        if end - start < 2 {
+            return;
+        }
+        let axis = self.widest_axis(start, end);
+        let mid = start + (end - start) / 2;
+        self.photons[start..end]
+            .select_nth_unstable_by(mid - start, |a, b| a.axis(axis).total_cmp(&b.axis(axis)));
+        self.axes[mid] = axis;
+        self.build(start, mid);
+        self.build(mid + 1, end);
+    }
+
+    fn widest_axis(&self, start: usize, end: usize) -> usize {
+        let mut best = (0, -1.0);
+        for axis in 0..3 {
+            let (mut lo, mut hi) = (Num::INFINITY, Num::NEG_INFINITY);
+            for p in &self.photons[start..end] {
+                lo = lo.min(p.axis(axis));
+                hi = hi.max(p.axis(axis));
+            }
+            if hi - lo > best.1 {
+                best = (axis, hi - lo);
+            }
+        }
+        best.0
+    }
+}
+
+impl PhotonMap {
+    pub fn len(&self) -> usize {
+        self.photons.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.photons.is_empty()
+    }
+    pub fn photons(&self) -> &[Photon] {
+        &self.photons
+    }
+    pub fn gather_count(&self) -> usize {
+        self.gather_count
+    }
+    pub fn gather_radius(&self) -> Num {
+        self.gather_radius
+    }
+    pub fn set_gather_count(&mut self, val: usize) {
+        self.gather_count = val.max(1);
+    }
+    pub fn set_gather_radius<T>(&mut self, val: T)
+    where
+        T: Into<Num>,
+    {
+        self.gather_radius = val.into();
+    }
+
+    // Up to `k` photons closest to `point` within `max_radius`, nearest first,
+    // paired with their squared distance.
+    pub fn nearest(&self, point: Tuple, k: usize, max_radius: Num) -> Vec<(Num, Photon)> {
+        let mut found = Vec::with_capacity(k + 1);
+        if k > 0 {
+            self.search(0, self.photons.len(), point, k, max_radius * max_radius, &mut found);
+        }
+        found
+    }
+
+    fn search(
+        &self,
+        start: usize,
+        end: usize,
+        point: Tuple,
+        k: usize,
+        max_dist2: Num,
+        found: &mut Vec<(Num, Photon)>,
+    ) {
+        if start >= end {
+            return;
+        }
+        let mid = start + (end - start) / 2;
+        let photon = self.photons[mid];
+        let axis = self.axes[mid];
+        let target = match axis {
+            0 => point.get_x(),
+            1 => point.get_y(),
+            _ => point.get_z(),
+        };
+        let delta = target - photon.axis(axis);
+        let (near, far) = if delta < 0.0 {
+            ((start, mid), (mid + 1, end))
+        } else {
+            ((mid + 1, end), (start, mid))
+        };
+
+        self.search(near.0, near.1, point, k, max_dist2, found);
+
+        let dist2 = {
+            let d = photon.position() - point;
+            d.dot(&d)
+        };
+        if dist2 <= max_dist2 {
+            let index = found.partition_point(|(d, _)| *d <= dist2);
+            if index < k {
+                found.insert(index, (dist2, photon));
+                found.truncate(k);
+            }
+        }
+
+        let radius2 = if found.len() == k {
+            found[k - 1].0.min(max_dist2)
+        } else {
+            max_dist2
+        };
+        if delta * delta <= radius2 {
+            self.search(far.0, far.1, point, k, max_dist2, found);
+        }
+    }
+
+    // Irradiance arriving at `point` from the front of `normal`, estimated
+    // from the nearest photons.
+    pub fn irradiance(&self, point: Tuple, normal: Tuple) -> Pixel {
+        let found = self.nearest(point, self.gather_count, self.gather_radius);
+        if found.is_empty() {
+            return Pixel::black();
+        }
+
+        let radius2 = if found.len() == self.gather_count {
+            found[found.len() - 1].0
+        } else {
+            self.gather_radius * self.gather_radius
+        };
+        if radius2 <= 0.0 {
+            return Pixel::black();
+        }
+
+        let mut flux = Pixel::black();
+        for (_, photon) in found {
+            if photon.direction().dot(&normal) < 0.0 {
+                flux = flux + photon.power();
+            }
+        }
+        flux * (1.0 / (PI * radius2))
+    }
+}
+
+impl World {
+    // Shoots photons from the light and keeps those that reached a diffuse
+    // surface after at least one mirror or refraction bounce.
+    //
+    // Photon power grows with the squared distance travelled, so unfocused
+    // photons reproduce the falloff free irradiance of the Phong lighting and
+    // only the focusing done by lenses and mirrors shows up as caustics.
+    pub fn emit_caustic_photons(&self, count: u32) -> PhotonMap {
+        let light = match self.light() {
+            Some(l) => l,
+            None => return PhotonMap::new(vec![]),
+        };
+        let mut sampler = Sampler::new(count as u64);
+        let emitted = light.intensity() * (4.0 * PI * PI / count.max(1) as Num);
+        let mut photons = vec![];
+
+        for _ in 0..count {
+            let mut ray = Ray::new(light.position(), sampler::uniform_sphere(&mut sampler));
+            let mut travelled = 0.0;
+            let mut specular = false;
+
+            for _ in 0..MAX_PHOTON_BOUNCES {
+                let xs = self.intersect_world_for(ray, RayKind::Shadow);
+                let h = match hit(xs.clone()) {
+                    Some(h) => h,
+                    None => break,
+                };
+                travelled += h.t();
+                let comps = h.prepare_computations(ray, xs);
+                let material = comps.object().material();
+                let reflective = material.reflective().clamp(0.0, 1.0);
+                let transparency = material.transparency().clamp(0.0, 1.0);
+
+                let base = (1.0 - reflective - transparency).max(0.0);
+                if specular && base > 0.0 && material.diffuse() > 0.0 {
+                    let power = emitted * (base * travelled * travelled);
+                    photons.push(Photon::new(comps.point(), ray.direction(), power));
+                }
+
+                let (mut mirror, mut refraction) = (reflective, transparency);
+                if reflective > 0.0 && transparency > 0.0 {
+                    let reflectance = comps.schlick();
+                    mirror *= reflectance;
+                    refraction *= 1.0 - reflectance;
+                }
+
+                let pick = sampler.next();
+                if pick < mirror {
+                    ray = Ray::new(comps.over_point(), comps.reflectv());
+                } else if pick < mirror + refraction {
+                    match comps.refracted_direction() {
+                        Some(direction) => ray = Ray::new(comps.under_point(), direction),
+                        None => break,
+                    }
+                } else {
+                    break;
+                }
+                specular = true;
+            }
+        }
+
+        PhotonMap::new(photons)
+    }
+}
@@ -1,5 +1,13 @@
 use crate::{matrix4x4::Matrix4x4, tuple::Tuple, Num};
 
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RayKind {
+    Camera,
+    Reflection,
+    Refraction,
+    Shadow,
+}
+
 #[derive(Clone, Copy)]
 pub struct Ray {
     origin: Tuple,
@@ -0,0 +1,126 @@
+use crate::Num;
+
+// Real roots of polynomials, coefficients highest degree first, in ascending
+// order.
+//
+// Closed forms like Ferrari's lose roots to cancellation once they cluster
+// away from zero, so beyond quadratics the roots are bracketed instead: they
+// lie between consecutive roots of the derivative (found the same way) and
+// are refined by Newton steps that fall back to bisection.
+
+pub(crate) fn solve_quartic(a: Num, b: Num, c: Num, d: Num, e: Num) -> Vec<Num> {
+    solve_polynomial(&[a, b, c, d, e])
+}
+
+pub(crate) fn solve_polynomial(coefficients: &[Num]) -> Vec<Num> {
+    // Leading terms are negligible relative to the largest coefficient, not
+    // in absolute terms, so uniformly tiny polynomials keep their roots.
+    let scale = coefficients.iter().fold(0.0, |m: Num, c| m.max(c.abs()));
+    let coefficients = match coefficients.iter().position(|c| c.abs() > scale * Num::EPSILON) {
+        Some(i) => &coefficients[i..],
+        None => return vec![],
+    };
+
+    match coefficients.len() {
+        0 | 1 => vec![],
+        2 => vec![-coefficients[1] / coefficients[0]],
+        3 => quadratic(coefficients[0], coefficients[1], coefficients[2]),
+        n => {
+            let derivative: Vec<Num> = coefficients[..n - 1]
+                .iter()
+                .enumerate()
+                .map(|(i, c)| c * (n - 1 - i) as Num)
+                .collect();
+            // Fujiwara's bound holds every root inside (-bound, bound).
+            let degree = (n - 1) as Num;
+            let bound = 2.0
+                * coefficients[1..].iter().enumerate().fold(0.0, |m: Num, (i, c)| {
+                    let ratio = (c / coefficients[0]).abs();
+                    let ratio = if i + 2 == n { ratio / 2.0 } else { ratio };
+                    m.max(ratio.powf(1.0 / (i + 1) as Num))
+                })
+                .max(Num::MIN_POSITIVE * degree);
+
+            let mut edges = vec![-bound];
+            edges.extend(solve_polynomial(&derivative).into_iter().filter(|c| c.abs() < bound));
+            edges.push(bound);
+
+            let mut roots: Vec<Num> = vec![];
+            for pair in edges.windows(2) {
+                if let Some(root) = bracketed_root(coefficients, pair[0], pair[1]) {
+                    if roots.last().is_none_or(|last| *last != root) {
+                        roots.push(root);
+                    }
+                }
+            }
+            roots
+        }
+    }
+}
+
+fn quadratic(a: Num, b: Num, c: Num) -> Vec<Num> {
+    let disc = b * b - 4.0 * a * c;
+    if disc < 0.0 {
+        return vec![];
+    }
+    // Avoids cancelling b against the square root.
+    let q = -0.5 * (b + b.signum() * disc.sqrt());
+    let mut roots = if q == 0.0 { vec![0.0, 0.0] } else { vec![q / a, c / q] };
+    roots.sort_by(|x, y| x.total_cmp(y));
+    roots
+}
+
+// The root in [lo, hi] when the polynomial changes sign across it (or
+// touches zero at an end).
+fn bracketed_root(coefficients: &[Num], lo: Num, hi: Num) -> Option<Num> {
+    let (f_lo, _) = evaluate(coefficients, lo);
+    let (f_hi, _) = evaluate(coefficients, hi);
+    if f_lo == 0.0 {
+        return Some(lo);
+    }
+    if f_hi == 0.0 {
+        return Some(hi);
+    }
+    if f_lo.signum() == f_hi.signum() {
+        return None;
+    }
+
+    let (mut lo, mut hi) = if f_lo < 0.0 { (lo, hi) } else { (hi, lo) };
+    let mut x = 0.5 * (lo + hi);
+    let mut last_step = (hi - lo).abs();
+    for _ in 0..200 {
+        let (f, df) = evaluate(coefficients, x);
+        if f == 0.0 {
+            return Some(x);
+        }
+        if f < 0.0 {
+            lo = x;
+        } else {
+            hi = x;
+        }
+        // Newton only while it stays inside the bracket and converges at
+        // least as fast as bisection would.
+        let newton = x - f / df;
+        let next = if df != 0.0 && (newton - lo) * (newton - hi) < 0.0 && (2.0 * f).abs() <= (last_step * df).abs() {
+            newton
+        } else {
+            0.5 * (lo + hi)
+        };
+        last_step = (next - x).abs();
+        if last_step <= 1e-15 * x.abs().max(1.0) {
+            return Some(next);
+        }
+        x = next;
+    }
+    Some(x)
+}
+
+// Value and derivative by Horner's scheme.
+fn evaluate(coefficients: &[Num], x: Num) -> (Num, Num) {
+    let (mut f, mut df) = (0.0, 0.0);
+    for &c in coefficients {
+        df = df * x + f;
+        f = f * x + c;
+    }
+    (f, df)
+}
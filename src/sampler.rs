@@ -0,0 +1,77 @@
+use crate::{tuple::Tuple, Num, PI};
+
+// Small xorshift64* generator so stochastic rendering stays reproducible
+// without pulling in an RNG dependency.
+#[derive(Clone, Copy)]
+pub(crate) struct Sampler {
+    state: u64,
+}
+
+impl Sampler {
+    pub(crate) fn new(seed: u64) -> Sampler {
+        // splitmix64 scrambles neighbouring seeds (pixel indices) into unrelated states
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        Sampler {
+            state: if z == 0 { 0x2545_F491_4F6C_DD1D } else { z },
+        }
+    }
+
+    pub(crate) fn for_pixel(x: u32, y: u32) -> Sampler {
+        Sampler::new(((x as u64) << 32) | y as u64)
+    }
+
+    // Seeded from the position so shading the same point twice gives the same result.
+    pub(crate) fn for_point(p: Tuple) -> Sampler {
+        let x = p.get_x().to_bits();
+        let y = p.get_y().to_bits().rotate_left(21);
+        let z = p.get_z().to_bits().rotate_left(42);
+        Sampler::new(x ^ y ^ z)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    // uniform in [0, 1)
+    pub(crate) fn next(&mut self) -> Num {
+        (self.next_u64() >> 11) as Num / (1u64 << 53) as Num
+    }
+}
+
+pub(crate) fn orthonormal_basis(n: Tuple) -> (Tuple, Tuple) {
+    let helper = if n.get_x().abs() > 0.9 {
+        Tuple::vector(0, 1, 0)
+    } else {
+        Tuple::vector(1, 0, 0)
+    };
+    let t = helper.cross(&n).normalize();
+    let b = n.cross(&t);
+    (t, b)
+}
+
+// Direction around `axis` with cos(theta) = cos_theta and azimuth phi.
+pub(crate) fn around_axis(axis: Tuple, cos_theta: Num, phi: Num) -> Tuple {
+    let (t, b) = orthonormal_basis(axis);
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    (t * (sin_theta * phi.cos()) + b * (sin_theta * phi.sin()) + axis * cos_theta).normalize()
+}
+
+// Cosine weighted direction in the hemisphere around `normal`, pdf = cos / PI.
+pub(crate) fn cosine_hemisphere(normal: Tuple, sampler: &mut Sampler) -> Tuple {
+    let u = sampler.next();
+    let phi = 2.0 * PI * sampler.next();
+    around_axis(normal, (1.0 - u).sqrt(), phi)
+}
+
+// Uniform direction on the unit sphere.
+pub(crate) fn uniform_sphere(sampler: &mut Sampler) -> Tuple {
+    let cos_theta = 1.0 - 2.0 * sampler.next();
+    let phi = 2.0 * PI * sampler.next();
+    around_axis(Tuple::vector(0, 1, 0), cos_theta, phi)
+}
@@ -1,55 +1,224 @@
+use std::sync::Arc;
+
 use crate::{
-    intersection::Intersection, material::Material, matrix4x4::Matrix4x4, ray::Ray, tuple::Tuple,
+    img::{Canvas, Pixel}, intersection::Intersection, material::Material, matrix4x4::Matrix4x4, ray::Ray,
+    transformation::Transform, tuple::Tuple, Num,
 };
 
+mod bounds;
+pub use bounds::Bounds;
+mod flags;
+pub use flags::ShapeFlags;
 mod sphere;
 pub use sphere::Sphere;
 mod test_shape;
-pub(crate) use test_shape::{TestShape, SAVED_RAY};
+pub(crate) use test_shape::TestShape;
+#[cfg(test)]
+pub(crate) use test_shape::SAVED_RAY;
 mod plane;
 pub use plane::Plane;
+mod cube;
+pub use cube::Cube;
+mod cylinder;
+pub use cylinder::Cylinder;
+mod cone;
+pub use cone::Cone;
+mod triangle;
+pub use triangle::{SmoothTriangle, Triangle};
+mod torus;
+pub use torus::Torus;
+mod disk;
+pub use disk::Disk;
+mod rectangle;
+pub use rectangle::Rectangle;
+mod sdf;
+pub use sdf::{Sdf, SdfShape};
+mod heightfield;
+pub use heightfield::Heightfield;
+mod metaballs;
+pub use metaballs::{Falloff, Metaball, Metaballs};
+mod quadric;
+pub use quadric::Quadric;
+mod custom;
+pub use custom::{CustomShape, ShapeGeometry};
+mod group;
+pub use group::Group;
+mod csg;
+pub use csg::{Csg, CsgOp};
+mod instance;
+pub use instance::Instance;
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub enum Shape {
     Sphere(Sphere),
     Plane(Plane),
+    Cube(Cube),
+    Cylinder(Cylinder),
+    Cone(Cone),
+    Custom(CustomShape),
+    Quadric(Quadric),
+    Metaballs(Metaballs),
+    Heightfield(Heightfield),
+    Sdf(SdfShape),
+    Rectangle(Rectangle),
+    Disk(Disk),
+    Torus(Torus),
+    Triangle(Triangle),
+    SmoothTriangle(SmoothTriangle),
+    Group(Group),
+    Csg(Csg),
+    Instance(Instance),
     Test(TestShape),
 }
 
 impl Shape {
     pub fn intersect(&self, ray: Ray) -> Vec<Intersection> {
-        let local_ray = ray.transform(self.transform().inverse().unwrap());
+        let local_ray = ray.transform(self.cached_transform().inverse());
         match self {
-            Shape::Sphere(item) => item.intersect(local_ray, *self),
-            Shape::Test(item) => item.intersect(local_ray, *self),
-            Shape::Plane(item) => item.intersect(local_ray, *self),
+            Shape::Sphere(item) => item.intersect(local_ray, self),
+            Shape::Test(item) => item.intersect(local_ray, self),
+            Shape::Plane(item) => item.intersect(local_ray, self),
+            Shape::Cube(item) => item.intersect(local_ray, self),
+            Shape::Cylinder(item) => item.intersect(local_ray, self),
+            Shape::Cone(item) => item.intersect(local_ray, self),
+            Shape::Custom(item) => item.intersect(local_ray, self),
+            Shape::Quadric(item) => item.intersect(local_ray, self),
+            Shape::Metaballs(item) => item.intersect(local_ray, self),
+            Shape::Heightfield(item) => item.intersect(local_ray, self),
+            Shape::Sdf(item) => item.intersect(local_ray, self),
+            Shape::Rectangle(item) => item.intersect(local_ray, self),
+            Shape::Disk(item) => item.intersect(local_ray, self),
+            Shape::Torus(item) => item.intersect(local_ray, self),
+            Shape::Triangle(item) => item.intersect(local_ray, self),
+            Shape::SmoothTriangle(item) => item.intersect(local_ray, self),
+            Shape::Group(item) => item.intersect(local_ray),
+            Shape::Csg(item) => item.intersect(local_ray),
+            Shape::Instance(item) => item.intersect(local_ray),
         }
     }
-    pub fn set_transform(&mut self, transform: Matrix4x4) {
+    // Fails, leaving the shape as it was, if the matrix can't be inverted.
+    pub fn set_transform(&mut self, transform: Matrix4x4) -> Result<(), String> {
+        self.set_cached_transform(Transform::new(transform)?);
+        Ok(())
+    }
+    pub(crate) fn set_cached_transform(&mut self, transform: Transform) {
         match self {
             Shape::Sphere(item) => item.set_transform(transform),
             Shape::Test(item) => item.set_transform(transform),
             Shape::Plane(item) => item.set_transform(transform),
+            Shape::Cube(item) => item.set_transform(transform),
+            Shape::Cylinder(item) => item.set_transform(transform),
+            Shape::Cone(item) => item.set_transform(transform),
+            Shape::Custom(item) => item.set_transform(transform),
+            Shape::Quadric(item) => item.set_transform(transform),
+            Shape::Metaballs(item) => item.set_transform(transform),
+            Shape::Heightfield(item) => item.set_transform(transform),
+            Shape::Sdf(item) => item.set_transform(transform),
+            Shape::Rectangle(item) => item.set_transform(transform),
+            Shape::Disk(item) => item.set_transform(transform),
+            Shape::Torus(item) => item.set_transform(transform),
+            Shape::Triangle(item) => item.set_transform(transform),
+            Shape::SmoothTriangle(item) => item.set_transform(transform),
+            Shape::Group(item) => item.set_transform(transform),
+            Shape::Csg(item) => item.set_transform(transform),
+            Shape::Instance(item) => item.set_transform(transform),
         }
     }
     pub fn transform(&self) -> Matrix4x4 {
+        self.cached_transform().matrix()
+    }
+    pub(crate) fn cached_transform(&self) -> &Transform {
         match self {
             Shape::Sphere(item) => item.transform(),
             Shape::Test(item) => item.transform(),
             Shape::Plane(item) => item.transform(),
+            Shape::Cube(item) => item.transform(),
+            Shape::Cylinder(item) => item.transform(),
+            Shape::Cone(item) => item.transform(),
+            Shape::Custom(item) => item.transform(),
+            Shape::Quadric(item) => item.transform(),
+            Shape::Metaballs(item) => item.transform(),
+            Shape::Heightfield(item) => item.transform(),
+            Shape::Sdf(item) => item.transform(),
+            Shape::Rectangle(item) => item.transform(),
+            Shape::Disk(item) => item.transform(),
+            Shape::Torus(item) => item.transform(),
+            Shape::Triangle(item) => item.transform(),
+            Shape::SmoothTriangle(item) => item.transform(),
+            Shape::Group(item) => item.transform(),
+            Shape::Csg(item) => item.transform(),
+            Shape::Instance(item) => item.transform(),
         }
     }
-    pub fn normal_at(&self, point: Tuple) -> Tuple {
-        let inv = self.transform().inverse().unwrap();
-
-        let local_point = inv * point;
+    // Bounds in the shape's own space, before its transform.
+    pub fn bounds(&self) -> Bounds {
+        match self {
+            Shape::Sphere(item) => item.bounds(),
+            Shape::Test(item) => item.bounds(),
+            Shape::Plane(item) => item.bounds(),
+            Shape::Cube(item) => item.bounds(),
+            Shape::Cylinder(item) => item.bounds(),
+            Shape::Cone(item) => item.bounds(),
+            Shape::Custom(item) => item.bounds(),
+            Shape::Quadric(item) => item.bounds(),
+            Shape::Metaballs(item) => item.bounds(),
+            Shape::Heightfield(item) => item.bounds(),
+            Shape::Sdf(item) => item.bounds(),
+            Shape::Rectangle(item) => item.bounds(),
+            Shape::Disk(item) => item.bounds(),
+            Shape::Torus(item) => item.bounds(),
+            Shape::Triangle(item) => item.bounds(),
+            Shape::SmoothTriangle(item) => item.bounds(),
+            Shape::Group(item) => item.bounds(),
+            Shape::Csg(item) => item.bounds(),
+            Shape::Instance(item) => item.bounds(),
+        }
+    }
+    // Bounds after the shape's transform. For a shape inside a group that is
+    // the group's space rather than the world's.
+    pub fn world_bounds(&self) -> Bounds {
+        self.bounds().transform(self.transform())
+    }
+    // Groups, CSG shapes and instances have no normal of their own, only
+    // their children do.
+    pub fn normal_at(&self, point: Tuple) -> Option<Tuple> {
+        self.normal_at_point(point, None)
+    }
+    pub fn normal_at_hit(&self, point: Tuple, hit: &Intersection) -> Option<Tuple> {
+        self.normal_at_point(point, Some(hit))
+    }
+    fn normal_at_point(&self, point: Tuple, hit: Option<&Intersection>) -> Option<Tuple> {
+        let local_point = self.world_to_object(point);
         let local_normal = match self {
             Shape::Sphere(item) => item.normal_at(local_point),
             Shape::Test(item) => item.normal_at(local_point),
             Shape::Plane(item) => item.normal_at(local_point),
+            Shape::Cube(item) => item.normal_at(local_point),
+            Shape::Cylinder(item) => item.normal_at(local_point),
+            Shape::Cone(item) => item.normal_at(local_point),
+            Shape::Custom(item) => item.normal_at(local_point),
+            Shape::Quadric(item) => item.normal_at(local_point),
+            Shape::Metaballs(item) => item.normal_at(local_point),
+            Shape::Heightfield(item) => item.normal_at(local_point),
+            Shape::Sdf(item) => item.normal_at(local_point),
+            Shape::Rectangle(item) => item.normal_at(local_point),
+            Shape::Disk(item) => item.normal_at(local_point),
+            Shape::Torus(item) => item.normal_at(local_point),
+            Shape::Triangle(item) => item.normal_at(local_point),
+            Shape::SmoothTriangle(item) => match hit {
+                Some(hit) => item.normal_at_uv(hit.u(), hit.v()),
+                None => item.normal_at(local_point),
+            },
+            Shape::Group(_) | Shape::Csg(_) | Shape::Instance(_) => return None,
         };
 
-        let mut world_normal = inv.transpose() * local_normal;
+        Some(self.normal_to_world(local_normal))
+    }
+    pub fn world_to_object(&self, point: Tuple) -> Tuple {
+        self.cached_transform().inverse() * point
+    }
+    pub fn normal_to_world(&self, normal: Tuple) -> Tuple {
+        let mut world_normal = self.cached_transform().inverse_transpose() * normal;
         world_normal.set_w(0);
 
         world_normal.normalize()
@@ -59,6 +228,22 @@ impl Shape {
             Shape::Sphere(item) => item.material(),
             Shape::Test(item) => item.material(),
             Shape::Plane(item) => item.material(),
+            Shape::Cube(item) => item.material(),
+            Shape::Cylinder(item) => item.material(),
+            Shape::Cone(item) => item.material(),
+            Shape::Custom(item) => item.material(),
+            Shape::Quadric(item) => item.material(),
+            Shape::Metaballs(item) => item.material(),
+            Shape::Heightfield(item) => item.material(),
+            Shape::Sdf(item) => item.material(),
+            Shape::Rectangle(item) => item.material(),
+            Shape::Disk(item) => item.material(),
+            Shape::Torus(item) => item.material(),
+            Shape::Triangle(item) => item.material(),
+            Shape::SmoothTriangle(item) => item.material(),
+            Shape::Group(item) => item.material(),
+            Shape::Csg(item) => item.material(),
+            Shape::Instance(item) => item.material(),
         }
     }
     pub fn set_material(&mut self, material: Material) {
@@ -66,6 +251,68 @@ impl Shape {
             Shape::Sphere(item) => item.set_material(material),
             Shape::Test(item) => item.set_material(material),
             Shape::Plane(item) => item.set_material(material),
+            Shape::Cube(item) => item.set_material(material),
+            Shape::Cylinder(item) => item.set_material(material),
+            Shape::Cone(item) => item.set_material(material),
+            Shape::Custom(item) => item.set_material(material),
+            Shape::Quadric(item) => item.set_material(material),
+            Shape::Metaballs(item) => item.set_material(material),
+            Shape::Heightfield(item) => item.set_material(material),
+            Shape::Sdf(item) => item.set_material(material),
+            Shape::Rectangle(item) => item.set_material(material),
+            Shape::Disk(item) => item.set_material(material),
+            Shape::Torus(item) => item.set_material(material),
+            Shape::Triangle(item) => item.set_material(material),
+            Shape::SmoothTriangle(item) => item.set_material(material),
+            Shape::Group(item) => item.set_material(material),
+            Shape::Csg(item) => item.set_material(material),
+            Shape::Instance(item) => item.set_material(material),
+        }
+    }
+    pub fn flags(&self) -> ShapeFlags {
+        match self {
+            Shape::Sphere(item) => item.flags(),
+            Shape::Test(item) => item.flags(),
+            Shape::Plane(item) => item.flags(),
+            Shape::Cube(item) => item.flags(),
+            Shape::Cylinder(item) => item.flags(),
+            Shape::Cone(item) => item.flags(),
+            Shape::Custom(item) => item.flags(),
+            Shape::Quadric(item) => item.flags(),
+            Shape::Metaballs(item) => item.flags(),
+            Shape::Heightfield(item) => item.flags(),
+            Shape::Sdf(item) => item.flags(),
+            Shape::Rectangle(item) => item.flags(),
+            Shape::Disk(item) => item.flags(),
+            Shape::Torus(item) => item.flags(),
+            Shape::Triangle(item) => item.flags(),
+            Shape::SmoothTriangle(item) => item.flags(),
+            Shape::Group(item) => item.flags(),
+            Shape::Csg(item) => item.flags(),
+            Shape::Instance(item) => item.flags(),
+        }
+    }
+    pub fn set_flags(&mut self, flags: ShapeFlags) {
+        match self {
+            Shape::Sphere(item) => item.set_flags(flags),
+            Shape::Test(item) => item.set_flags(flags),
+            Shape::Plane(item) => item.set_flags(flags),
+            Shape::Cube(item) => item.set_flags(flags),
+            Shape::Cylinder(item) => item.set_flags(flags),
+            Shape::Cone(item) => item.set_flags(flags),
+            Shape::Custom(item) => item.set_flags(flags),
+            Shape::Quadric(item) => item.set_flags(flags),
+            Shape::Metaballs(item) => item.set_flags(flags),
+            Shape::Heightfield(item) => item.set_flags(flags),
+            Shape::Sdf(item) => item.set_flags(flags),
+            Shape::Rectangle(item) => item.set_flags(flags),
+            Shape::Disk(item) => item.set_flags(flags),
+            Shape::Torus(item) => item.set_flags(flags),
+            Shape::Triangle(item) => item.set_flags(flags),
+            Shape::SmoothTriangle(item) => item.set_flags(flags),
+            Shape::Group(item) => item.set_flags(flags),
+            Shape::Csg(item) => item.set_flags(flags),
+            Shape::Instance(item) => item.set_flags(flags),
         }
     }
 
@@ -78,6 +325,151 @@ impl Shape {
     pub fn plane() -> Shape {
         Shape::Plane(Plane::new())
     }
+    pub fn cube() -> Shape {
+        Shape::Cube(Cube::new())
+    }
+    pub fn cylinder() -> Shape {
+        Shape::Cylinder(Cylinder::new())
+    }
+    pub fn truncated_cylinder<T1, T2>(minimum: T1, maximum: T2, closed: bool) -> Shape
+    where
+        T1: Into<Num>,
+        T2: Into<Num>,
+    {
+        Shape::Cylinder(Cylinder::truncated(minimum.into(), maximum.into(), closed))
+    }
+    pub fn cone() -> Shape {
+        Shape::Cone(Cone::new())
+    }
+    pub fn truncated_cone<T1, T2>(minimum: T1, maximum: T2, closed: bool) -> Shape
+    where
+        T1: Into<Num>,
+        T2: Into<Num>,
+    {
+        Shape::Cone(Cone::truncated(minimum.into(), maximum.into(), closed))
+    }
+    pub fn disk<T>(radius: T) -> Shape
+    where
+        T: Into<Num>,
+    {
+        Shape::Disk(Disk::new(radius.into()))
+    }
+    pub fn annulus<T1, T2>(inner_radius: T1, radius: T2) -> Shape
+    where
+        T1: Into<Num>,
+        T2: Into<Num>,
+    {
+        Shape::Disk(Disk::annulus(inner_radius.into(), radius.into()))
+    }
+    pub fn rectangle<T1, T2>(width: T1, depth: T2) -> Shape
+    where
+        T1: Into<Num>,
+        T2: Into<Num>,
+    {
+        Shape::Rectangle(Rectangle::new(width.into(), depth.into()))
+    }
+    pub fn heightfield(heights: Vec<Vec<Num>>) -> Result<Shape, String> {
+        Ok(Shape::Heightfield(Heightfield::new(heights)?))
+    }
+    pub fn heightfield_from_canvas(canvas: &Canvas) -> Result<Shape, String> {
+        Ok(Shape::Heightfield(Heightfield::from_canvas(canvas)?))
+    }
+    pub fn metaballs<T>(balls: Vec<Metaball>, threshold: T, falloff: Falloff) -> Result<Shape, String>
+    where
+        T: Into<Num>,
+    {
+        Ok(Shape::Metaballs(Metaballs::new(balls, threshold.into(), falloff)?))
+    }
+    pub fn custom<G>(geometry: G) -> Shape
+    where
+        G: ShapeGeometry + 'static,
+    {
+        Shape::Custom(CustomShape::new(geometry))
+    }
+    pub fn quadric(coefficients: Matrix4x4) -> Shape {
+        Shape::Quadric(Quadric::new(coefficients))
+    }
+    pub fn ellipsoid<T1, T2, T3>(a: T1, b: T2, c: T3) -> Shape
+    where
+        T1: Into<Num>,
+        T2: Into<Num>,
+        T3: Into<Num>,
+    {
+        Shape::Quadric(Quadric::ellipsoid(a, b, c))
+    }
+    pub fn sdf(sdf: Sdf) -> Shape {
+        Shape::Sdf(SdfShape::new(sdf))
+    }
+    pub fn torus<T1, T2>(major_radius: T1, minor_radius: T2) -> Shape
+    where
+        T1: Into<Num>,
+        T2: Into<Num>,
+    {
+        Shape::Torus(Torus::new(major_radius.into(), minor_radius.into()))
+    }
+    pub fn triangle(p1: Tuple, p2: Tuple, p3: Tuple) -> Shape {
+        Shape::Triangle(Triangle::new(p1, p2, p3))
+    }
+    pub fn group() -> Shape {
+        Shape::Group(Group::new(vec![]))
+    }
+    pub fn group_of(children: Vec<Shape>) -> Shape {
+        Shape::Group(Group::new(children))
+    }
+
+    pub fn csg(op: CsgOp, left: Shape, right: Shape) -> Shape {
+        Shape::Csg(Csg::new(op, left, right))
+    }
+
+    // Places `target` again without copying it. Set the instance's own
+    // transform and material to move it and restyle everything in it.
+    pub fn instance(target: Arc<Shape>) -> Shape {
+        Shape::Instance(Instance::new(target))
+    }
+
+    pub fn add_child(&mut self, child: Shape) -> Result<(), String> {
+        match self {
+            Shape::Group(item) => item.add_child(child),
+            _ => return Err("only groups can have children".to_string()),
+        }
+        Ok(())
+    }
+    pub fn children(&self) -> &[Shape] {
+        match self {
+            Shape::Group(item) => item.children(),
+            _ => &[],
+        }
+    }
+
+    // Colors at the vertices of a (smooth) triangle, blended across its face
+    // in place of the material color.
+    pub fn set_vertex_colors(&mut self, c1: Pixel, c2: Pixel, c3: Pixel) -> Result<(), String> {
+        match self {
+            Shape::Triangle(item) => item.set_vertex_colors(Some([c1, c2, c3])),
+            Shape::SmoothTriangle(item) => item.set_vertex_colors(Some([c1, c2, c3])),
+            _ => return Err("only triangles have vertex colors".to_string()),
+        }
+        Ok(())
+    }
+    pub fn vertex_color_at(&self, point: Tuple) -> Option<Pixel> {
+        let local_point = self.world_to_object(point);
+        match self {
+            Shape::Triangle(item) => item.color_at(local_point),
+            Shape::SmoothTriangle(item) => item.color_at(local_point),
+            _ => None,
+        }
+    }
+
+    pub fn smooth_triangle(
+        p1: Tuple,
+        p2: Tuple,
+        p3: Tuple,
+        n1: Tuple,
+        n2: Tuple,
+        n3: Tuple,
+    ) -> Shape {
+        Shape::SmoothTriangle(SmoothTriangle::new(p1, p2, p3, n1, n2, n3))
+    }
 }
 
 impl PartialEq for Shape {
@@ -86,6 +478,22 @@ impl PartialEq for Shape {
             (Self::Sphere(l0), Self::Sphere(r0)) => l0 == r0,
             (Self::Test(l0), Self::Test(r0)) => l0 == r0,
             (Self::Plane(l0), Self::Plane(r0)) => l0 == r0,
+            (Self::Cube(l0), Self::Cube(r0)) => l0 == r0,
+            (Self::Cylinder(l0), Self::Cylinder(r0)) => l0 == r0,
+            (Self::Cone(l0), Self::Cone(r0)) => l0 == r0,
+            (Self::Custom(l0), Self::Custom(r0)) => l0 == r0,
+            (Self::Quadric(l0), Self::Quadric(r0)) => l0 == r0,
+            (Self::Metaballs(l0), Self::Metaballs(r0)) => l0 == r0,
+            (Self::Heightfield(l0), Self::Heightfield(r0)) => l0 == r0,
+            (Self::Sdf(l0), Self::Sdf(r0)) => l0 == r0,
+            (Self::Rectangle(l0), Self::Rectangle(r0)) => l0 == r0,
+            (Self::Disk(l0), Self::Disk(r0)) => l0 == r0,
+            (Self::Torus(l0), Self::Torus(r0)) => l0 == r0,
+            (Self::Triangle(l0), Self::Triangle(r0)) => l0 == r0,
+            (Self::SmoothTriangle(l0), Self::SmoothTriangle(r0)) => l0 == r0,
+            (Self::Group(l0), Self::Group(r0)) => l0 == r0,
+            (Self::Csg(l0), Self::Csg(r0)) => l0 == r0,
+            (Self::Instance(l0), Self::Instance(r0)) => l0 == r0,
             _ => false,
         }
     }
@@ -0,0 +1,174 @@
+use crate::{Matrix4x4, Num, Ray, Tuple};
+
+// Axis aligned bounding box. Infinite extents stand for unbounded shapes
+// like planes; an empty box has min above max.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Bounds {
+    min: Tuple,
+    max: Tuple,
+}
+
+impl Bounds {
+    pub fn new(min: Tuple, max: Tuple) -> Bounds {
+        Bounds { min, max }
+    }
+    pub fn empty() -> Bounds {
+        Bounds::new(
+            Tuple::point(Num::INFINITY, Num::INFINITY, Num::INFINITY),
+            Tuple::point(Num::NEG_INFINITY, Num::NEG_INFINITY, Num::NEG_INFINITY),
+        )
+    }
+    pub fn infinite() -> Bounds {
+        Bounds::new(
+            Tuple::point(Num::NEG_INFINITY, Num::NEG_INFINITY, Num::NEG_INFINITY),
+            Tuple::point(Num::INFINITY, Num::INFINITY, Num::INFINITY),
+        )
+    }
+    pub(crate) fn of_points(points: &[Tuple]) -> Bounds {
+        let mut bounds = Bounds::empty();
+        for &p in points {
+            bounds.add_point(p);
+        }
+        bounds
+    }
+
+    pub fn min(&self) -> Tuple {
+        self.min
+    }
+    pub fn max(&self) -> Tuple {
+        self.max
+    }
+    // Extent along each axis, infinite for unbounded ones.
+    pub fn size(&self) -> Tuple {
+        if self.is_empty() {
+            return Tuple::vector(0, 0, 0);
+        }
+        self.max - self.min
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (0..3).any(|axis| axis_of(self.min, axis) > axis_of(self.max, axis))
+    }
+    pub fn is_finite(&self) -> bool {
+        (0..3).all(|axis| axis_of(self.min, axis).is_finite() && axis_of(self.max, axis).is_finite())
+    }
+
+    pub fn contains_point(&self, p: Tuple) -> bool {
+        (0..3).all(|axis| axis_of(self.min, axis) <= axis_of(p, axis) && axis_of(p, axis) <= axis_of(self.max, axis))
+    }
+
+    pub fn add_point(&mut self, p: Tuple) {
+        self.min = Tuple::point(
+            self.min.get_x().min(p.get_x()),
+            self.min.get_y().min(p.get_y()),
+            self.min.get_z().min(p.get_z()),
+        );
+        self.max = Tuple::point(
+            self.max.get_x().max(p.get_x()),
+            self.max.get_y().max(p.get_y()),
+            self.max.get_z().max(p.get_z()),
+        );
+    }
+    pub fn merge(&self, other: &Bounds) -> Bounds {
+        let mut bounds = *self;
+        bounds.add_point(other.min);
+        bounds.add_point(other.max);
+        bounds
+    }
+    // Part of the box also inside the other one.
+    pub(crate) fn overlap(&self, other: &Bounds) -> Bounds {
+        Bounds::new(
+            Tuple::point(
+                self.min.get_x().max(other.min.get_x()),
+                self.min.get_y().max(other.min.get_y()),
+                self.min.get_z().max(other.min.get_z()),
+            ),
+            Tuple::point(
+                self.max.get_x().min(other.max.get_x()),
+                self.max.get_y().min(other.max.get_y()),
+                self.max.get_z().min(other.max.get_z()),
+            ),
+        )
+    }
+    pub(crate) fn padded(&self, by: Num) -> Bounds {
+        Bounds::new(self.min - Tuple::vector(by, by, by), self.max + Tuple::vector(by, by, by))
+    }
+
+    // Arvo's method: each output extent sums the smaller and larger products
+    // of a matrix row with the input extents. Taking 0 * inf as 0 keeps
+    // unbounded axes that the transform doesn't mix in (a translated plane
+    // still has finite height) while rotations spread them everywhere.
+    pub fn transform(&self, m: Matrix4x4) -> Bounds {
+        if self.is_empty() {
+            return *self;
+        }
+        let mul = |a: Num, b: Num| if a == 0.0 { 0.0 } else { a * b };
+        let (mut min, mut max) = ([0.0; 3], [0.0; 3]);
+        for row in 0..3 {
+            min[row] = m.at(row, 3);
+            max[row] = m.at(row, 3);
+            for col in 0..3 {
+                let a = mul(m.at(row, col), axis_of(self.min, col));
+                let b = mul(m.at(row, col), axis_of(self.max, col));
+                min[row] += a.min(b);
+                max[row] += a.max(b);
+            }
+        }
+        Bounds::new(Tuple::point(min[0], min[1], min[2]), Tuple::point(max[0], max[1], max[2]))
+    }
+
+    pub fn centroid(&self) -> Tuple {
+        Tuple::point(
+            (self.min.get_x() + self.max.get_x()) / 2.0,
+            (self.min.get_y() + self.max.get_y()) / 2.0,
+            (self.min.get_z() + self.max.get_z()) / 2.0,
+        )
+    }
+    pub(crate) fn surface_area(&self) -> Num {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let d = self.max - self.min;
+        2.0 * (d.get_x() * d.get_y() + d.get_y() * d.get_z() + d.get_z() * d.get_x())
+    }
+
+    // Slab test along the whole line of the ray, behind its origin too, since
+    // refraction bookkeeping looks at every crossing.
+    pub fn intersects(&self, ray: Ray) -> bool {
+        self.ray_span(ray).is_some()
+    }
+
+    // Range of t over which the ray's line is inside the box.
+    pub(crate) fn ray_span(&self, ray: Ray) -> Option<(Num, Num)> {
+        let (mut tmin, mut tmax) = (Num::NEG_INFINITY, Num::INFINITY);
+        for axis in 0..3 {
+            let origin = axis_of(ray.origin(), axis);
+            let direction = axis_of(ray.direction(), axis);
+            let (lo, hi) = (axis_of(self.min, axis), axis_of(self.max, axis));
+            if direction == 0.0 {
+                if origin < lo || origin > hi {
+                    return None;
+                }
+                continue;
+            }
+            let (mut t0, mut t1) = ((lo - origin) / direction, (hi - origin) / direction);
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            tmin = tmin.max(t0);
+            tmax = tmax.min(t1);
+            if tmin > tmax {
+                return None;
+            }
+        }
+        Some((tmin, tmax))
+    }
+}
+
+fn axis_of(t: Tuple, axis: usize) -> Num {
+    match axis {
+        0 => t.get_x(),
+        1 => t.get_y(),
+        _ => t.get_z(),
+    }
+}
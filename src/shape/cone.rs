@@ -0,0 +1,148 @@
+use super::Bounds;
+use crate::{equal, transformation::Transform, Intersection, Material, Num, Ray, Shape, ShapeFlags, Tuple, EPSILON};
+
+#[derive(Clone, Copy)]
+pub struct Cone {
+    matrix: Transform,
+    material: Material,
+    flags: ShapeFlags,
+    minimum: Num,
+    maximum: Num,
+    closed: bool,
+}
+
+impl Cone {
+    pub fn new() -> Cone {
+        Self::truncated(Num::NEG_INFINITY, Num::INFINITY, false)
+    }
+    pub fn truncated(minimum: Num, maximum: Num, closed: bool) -> Cone {
+        Self {
+            matrix: Transform::identity(),
+            material: Material::default(),
+            flags: ShapeFlags::default(),
+            minimum,
+            maximum,
+            closed,
+        }
+    }
+
+    pub fn minimum(&self) -> Num {
+        self.minimum
+    }
+    pub fn maximum(&self) -> Num {
+        self.maximum
+    }
+    pub fn closed(&self) -> bool {
+        self.closed
+    }
+
+    pub(super) fn intersect(&self, ray: Ray, shape: &Shape) -> Vec<Intersection> {
+        let mut xs = vec![];
+        let o = ray.origin();
+        let d = ray.direction();
+
+        let a = d.get_x().powi(2) - d.get_y().powi(2) + d.get_z().powi(2);
+        let b = 2.0 * o.get_x() * d.get_x() - 2.0 * o.get_y() * d.get_y()
+            + 2.0 * o.get_z() * d.get_z();
+        let c = o.get_x().powi(2) - o.get_y().powi(2) + o.get_z().powi(2);
+
+        let ts = if equal(a, 0.0) {
+            // parallel to one half of the cone: a single hit on the other half
+            if equal(b, 0.0) {
+                vec![]
+            } else {
+                vec![-c / (2.0 * b)]
+            }
+        } else {
+            let disc = b * b - 4.0 * a * c;
+            if disc < 0.0 {
+                return xs;
+            }
+            let t0 = (-b - disc.sqrt()) / (2.0 * a);
+            let t1 = (-b + disc.sqrt()) / (2.0 * a);
+            vec![t0.min(t1), t0.max(t1)]
+        };
+
+        for t in ts {
+            let y = o.get_y() + t * d.get_y();
+            if self.minimum < y && y < self.maximum {
+                xs.push(Intersection::new(t, shape.clone()));
+            }
+        }
+
+        self.intersect_caps(ray, shape, &mut xs);
+        xs.sort_by(|a, b| a.t().partial_cmp(&b.t()).unwrap());
+        xs
+    }
+
+    fn intersect_caps(&self, ray: Ray, shape: &Shape, xs: &mut Vec<Intersection>) {
+        if !self.closed || equal(ray.direction().get_y(), 0.0) {
+            return;
+        }
+
+        for y in [self.minimum, self.maximum] {
+            let t = (y - ray.origin().get_y()) / ray.direction().get_y();
+            if check_cap(ray, t, y.abs()) {
+                xs.push(Intersection::new(t, shape.clone()));
+            }
+        }
+    }
+
+    pub(super) fn set_transform(&mut self, m: Transform) {
+        self.matrix = m;
+    }
+    pub(super) fn transform(&self) -> &Transform {
+        &self.matrix
+    }
+    pub(super) fn bounds(&self) -> Bounds {
+        let r = self.minimum.abs().max(self.maximum.abs());
+        Bounds::new(Tuple::point(-r, self.minimum, -r), Tuple::point(r, self.maximum, r))
+    }
+    pub(super) fn normal_at(&self, object_point: Tuple) -> Tuple {
+        let x = object_point.get_x();
+        let y = object_point.get_y();
+        let z = object_point.get_z();
+        let dist = x.powi(2) + z.powi(2);
+
+        if dist < y.powi(2) && y >= self.maximum - EPSILON {
+            Tuple::vector(0, 1, 0)
+        } else if dist < y.powi(2) && y <= self.minimum + EPSILON {
+            Tuple::vector(0, -1, 0)
+        } else if dist < EPSILON * EPSILON && y.abs() < EPSILON {
+            // the apex has no defined normal, fall back to the cone's axis
+            Tuple::vector(0, 1, 0)
+        } else {
+            let radial = if y > 0.0 { -dist.sqrt() } else { dist.sqrt() };
+            Tuple::vector(x, radial, z)
+        }
+    }
+    pub(super) fn material(&self) -> Material {
+        self.material
+    }
+    pub(super) fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+    pub(super) fn flags(&self) -> ShapeFlags {
+        self.flags
+    }
+    pub(super) fn set_flags(&mut self, flags: ShapeFlags) {
+        self.flags = flags;
+    }
+}
+
+fn check_cap(ray: Ray, t: Num, radius: Num) -> bool {
+    let x = ray.origin().get_x() + t * ray.direction().get_x();
+    let z = ray.origin().get_z() + t * ray.direction().get_z();
+    x.powi(2) + z.powi(2) <= radius.powi(2)
+}
+
+impl PartialEq for Cone {
+    fn eq(&self, other: &Self) -> bool {
+        self.transform() == other.transform()
+            && self.material() == other.material()
+            && self.flags() == other.flags()
+            && self.minimum == other.minimum
+            && self.maximum == other.maximum
+            && self.closed == other.closed
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use super::{group::adopt, Bounds};
+use crate::{transformation::Transform, Intersection, Material, Ray, Shape, ShapeFlags};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl CsgOp {
+    // Whether a hit on the left (or right) child survives, given whether the
+    // ray is currently inside the left and right children.
+    pub fn intersection_allowed(&self, lhit: bool, inl: bool, inr: bool) -> bool {
+        match self {
+            CsgOp::Union => (lhit && !inr) || (!lhit && !inl),
+            CsgOp::Intersection => (lhit && inr) || (!lhit && inl),
+            CsgOp::Difference => (lhit && !inr) || (!lhit && inl),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Csg {
+    matrix: Transform,
+    material: Material,
+    flags: ShapeFlags,
+    op: CsgOp,
+    left: Arc<Shape>,
+    right: Arc<Shape>,
+}
+
+impl Csg {
+    pub fn new(op: CsgOp, left: Shape, right: Shape) -> Csg {
+        Self {
+            matrix: Transform::identity(),
+            material: Material::default(),
+            flags: ShapeFlags::default(),
+            op,
+            left: Arc::new(left),
+            right: Arc::new(right),
+        }
+    }
+
+    pub fn operation(&self) -> CsgOp {
+        self.op
+    }
+    pub fn left(&self) -> &Shape {
+        &self.left
+    }
+    pub fn right(&self) -> &Shape {
+        &self.right
+    }
+
+    pub(super) fn intersect(&self, ray: Ray) -> Vec<Intersection> {
+        let mut tagged: Vec<(bool, Intersection)> = vec![];
+        tagged.extend(self.left.intersect(ray).into_iter().map(|i| (true, i)));
+        tagged.extend(self.right.intersect(ray).into_iter().map(|i| (false, i)));
+        tagged.sort_by(|a, b| a.1.t().partial_cmp(&b.1.t()).unwrap());
+        self.filter_intersections(tagged)
+            .into_iter()
+            .map(|i| adopt(i, self.matrix, self.flags))
+            .collect()
+    }
+
+    // Walks the merged hits of both children in order, tracking whether the
+    // ray is inside each, and keeps the ones on the combined surface.
+    fn filter_intersections(&self, xs: Vec<(bool, Intersection)>) -> Vec<Intersection> {
+        let (mut inl, mut inr) = (false, false);
+        let mut result = vec![];
+        for (lhit, i) in xs {
+            if self.op.intersection_allowed(lhit, inl, inr) {
+                result.push(i);
+            }
+            if lhit {
+                inl = !inl;
+            } else {
+                inr = !inr;
+            }
+        }
+        result
+    }
+
+    // Nothing of a difference reaches outside its left side.
+    pub(super) fn bounds(&self) -> Bounds {
+        let left = self.left.world_bounds();
+        match self.op {
+            CsgOp::Difference => left,
+            _ => left.merge(&self.right.world_bounds()),
+        }
+    }
+    pub(super) fn set_transform(&mut self, m: Transform) {
+        self.matrix = m;
+    }
+    pub(super) fn transform(&self) -> &Transform {
+        &self.matrix
+    }
+    pub(super) fn material(&self) -> Material {
+        self.material
+    }
+    // Like groups, the material reaches both children.
+    pub(super) fn set_material(&mut self, material: Material) {
+        self.material = material;
+        Arc::make_mut(&mut self.left).set_material(material);
+        Arc::make_mut(&mut self.right).set_material(material);
+    }
+    pub(super) fn flags(&self) -> ShapeFlags {
+        self.flags
+    }
+    pub(super) fn set_flags(&mut self, flags: ShapeFlags) {
+        self.flags = flags;
+    }
+}
+
+impl PartialEq for Csg {
+    fn eq(&self, other: &Self) -> bool {
+        self.transform() == other.transform()
+            && self.material() == other.material()
+            && self.flags() == other.flags()
+            && self.op == other.op
+            && self.left == other.left
+            && self.right == other.right
+    }
+}
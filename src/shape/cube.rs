@@ -0,0 +1,101 @@
+use super::Bounds;
+use crate::{transformation::Transform, Intersection, Material, Num, Ray, Shape, ShapeFlags, Tuple, EPSILON};
+
+#[derive(Clone, Copy)]
+pub struct Cube {
+    matrix: Transform,
+    material: Material,
+    flags: ShapeFlags,
+}
+
+impl Cube {
+    pub fn new() -> Cube {
+        Self {
+            matrix: Transform::identity(),
+            material: Material::default(),
+            flags: ShapeFlags::default(),
+        }
+    }
+
+    pub(super) fn intersect(&self, ray: Ray, shape: &Shape) -> Vec<Intersection> {
+        let (xtmin, xtmax) = check_axis(ray.origin().get_x(), ray.direction().get_x());
+        let (ytmin, ytmax) = check_axis(ray.origin().get_y(), ray.direction().get_y());
+        let (ztmin, ztmax) = check_axis(ray.origin().get_z(), ray.direction().get_z());
+
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+
+        if tmin > tmax {
+            return vec![];
+        }
+
+        Intersection::make(
+            Intersection::new(tmin, shape.clone()),
+            Intersection::new(tmax, shape.clone()),
+        )
+    }
+
+    pub(super) fn set_transform(&mut self, m: Transform) {
+        self.matrix = m;
+    }
+    pub(super) fn transform(&self) -> &Transform {
+        &self.matrix
+    }
+    pub(super) fn bounds(&self) -> Bounds {
+        Bounds::new(Tuple::point(-1, -1, -1), Tuple::point(1, 1, 1))
+    }
+    pub(super) fn normal_at(&self, object_point: Tuple) -> Tuple {
+        let x = object_point.get_x();
+        let y = object_point.get_y();
+        let z = object_point.get_z();
+        let maxc = x.abs().max(y.abs()).max(z.abs());
+
+        if maxc == x.abs() {
+            Tuple::vector(x, 0, 0)
+        } else if maxc == y.abs() {
+            Tuple::vector(0, y, 0)
+        } else {
+            Tuple::vector(0, 0, z)
+        }
+    }
+    pub(super) fn material(&self) -> Material {
+        self.material
+    }
+    pub(super) fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+    pub(super) fn flags(&self) -> ShapeFlags {
+        self.flags
+    }
+    pub(super) fn set_flags(&mut self, flags: ShapeFlags) {
+        self.flags = flags;
+    }
+}
+
+fn check_axis(origin: Num, direction: Num) -> (Num, Num) {
+    let tmin_numerator = -1.0 - origin;
+    let tmax_numerator = 1.0 - origin;
+
+    let (tmin, tmax) = if direction.abs() >= EPSILON {
+        (tmin_numerator / direction, tmax_numerator / direction)
+    } else {
+        (
+            tmin_numerator * Num::INFINITY,
+            tmax_numerator * Num::INFINITY,
+        )
+    };
+
+    if tmin > tmax {
+        (tmax, tmin)
+    } else {
+        (tmin, tmax)
+    }
+}
+
+impl PartialEq for Cube {
+    fn eq(&self, other: &Self) -> bool {
+        self.transform() == other.transform()
+            && self.material() == other.material()
+            && self.flags() == other.flags()
+    }
+}
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use super::Bounds;
+use crate::{transformation::Transform, Intersection, Material, Num, Ray, Shape, ShapeFlags, Tuple};
+
+// Geometry defined outside the crate. Everything is in object space: the
+// shape's transform, material and flags are handled by `Shape` as for the
+// built-in shapes.
+pub trait ShapeGeometry: Send + Sync {
+    // Every t where the ray crosses the surface, in any order and including
+    // negative ones, which refraction needs to tell inside from outside.
+    fn local_intersect(&self, ray: Ray) -> Vec<Num>;
+    fn local_normal_at(&self, point: Tuple) -> Tuple;
+    // Box around the surface, used to cull rays. Unbounded by default.
+    fn bounds(&self) -> Bounds {
+        Bounds::infinite()
+    }
+    // Surface coordinates of a point, reported on its intersections.
+    fn uv_at(&self, _point: Tuple) -> Option<(Num, Num)> {
+        None
+    }
+}
+
+#[derive(Clone)]
+pub struct CustomShape {
+    matrix: Transform,
+    material: Material,
+    flags: ShapeFlags,
+    geometry: Arc<dyn ShapeGeometry>,
+}
+
+impl CustomShape {
+    pub fn new<G>(geometry: G) -> CustomShape
+    where
+        G: ShapeGeometry + 'static,
+    {
+        Self {
+            matrix: Transform::identity(),
+            material: Material::default(),
+            flags: ShapeFlags::default(),
+            geometry: Arc::new(geometry),
+        }
+    }
+
+    pub fn geometry(&self) -> &dyn ShapeGeometry {
+        &*self.geometry
+    }
+
+    pub(super) fn intersect(&self, ray: Ray, shape: &Shape) -> Vec<Intersection> {
+        self.geometry
+            .local_intersect(ray)
+            .into_iter()
+            .map(|t| {
+                let (u, v) = self.geometry.uv_at(ray.position(t)).unwrap_or((0.0, 0.0));
+                Intersection::with_uv(t, shape.clone(), u, v)
+            })
+            .collect()
+    }
+
+    pub(super) fn set_transform(&mut self, m: Transform) {
+        self.matrix = m;
+    }
+    pub(super) fn transform(&self) -> &Transform {
+        &self.matrix
+    }
+    pub(super) fn bounds(&self) -> Bounds {
+        self.geometry.bounds()
+    }
+    pub(super) fn normal_at(&self, object_point: Tuple) -> Tuple {
+        self.geometry.local_normal_at(object_point)
+    }
+    pub(super) fn material(&self) -> Material {
+        self.material
+    }
+    pub(super) fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+    pub(super) fn flags(&self) -> ShapeFlags {
+        self.flags
+    }
+    pub(super) fn set_flags(&mut self, flags: ShapeFlags) {
+        self.flags = flags;
+    }
+}
+
+// Geometry can't be compared, so only copies of the same shape are equal.
+impl PartialEq for CustomShape {
+    fn eq(&self, other: &Self) -> bool {
+        self.transform() == other.transform()
+            && self.material() == other.material()
+            && self.flags() == other.flags()
+            && Arc::ptr_eq(&self.geometry, &other.geometry)
+    }
+}
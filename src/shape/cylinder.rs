@@ -0,0 +1,137 @@
+use super::Bounds;
+use crate::{equal, transformation::Transform, Intersection, Material, Num, Ray, Shape, ShapeFlags, Tuple, EPSILON};
+
+#[derive(Clone, Copy)]
+pub struct Cylinder {
+    matrix: Transform,
+    material: Material,
+    flags: ShapeFlags,
+    minimum: Num,
+    maximum: Num,
+    closed: bool,
+}
+
+impl Cylinder {
+    pub fn new() -> Cylinder {
+        Self::truncated(Num::NEG_INFINITY, Num::INFINITY, false)
+    }
+    pub fn truncated(minimum: Num, maximum: Num, closed: bool) -> Cylinder {
+        Self {
+            matrix: Transform::identity(),
+            material: Material::default(),
+            flags: ShapeFlags::default(),
+            minimum,
+            maximum,
+            closed,
+        }
+    }
+
+    pub fn minimum(&self) -> Num {
+        self.minimum
+    }
+    pub fn maximum(&self) -> Num {
+        self.maximum
+    }
+    pub fn closed(&self) -> bool {
+        self.closed
+    }
+
+    pub(super) fn intersect(&self, ray: Ray, shape: &Shape) -> Vec<Intersection> {
+        let mut xs = vec![];
+        let o = ray.origin();
+        let d = ray.direction();
+
+        let a = d.get_x().powi(2) + d.get_z().powi(2);
+        if !equal(a, 0.0) {
+            let b = 2.0 * o.get_x() * d.get_x() + 2.0 * o.get_z() * d.get_z();
+            let c = o.get_x().powi(2) + o.get_z().powi(2) - 1.0;
+            let disc = b * b - 4.0 * a * c;
+            if disc < 0.0 {
+                return xs;
+            }
+
+            let mut t0 = (-b - disc.sqrt()) / (2.0 * a);
+            let mut t1 = (-b + disc.sqrt()) / (2.0 * a);
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            for t in [t0, t1] {
+                let y = o.get_y() + t * d.get_y();
+                if self.minimum < y && y < self.maximum {
+                    xs.push(Intersection::new(t, shape.clone()));
+                }
+            }
+        }
+
+        self.intersect_caps(ray, shape, &mut xs);
+        xs.sort_by(|a, b| a.t().partial_cmp(&b.t()).unwrap());
+        xs
+    }
+
+    fn intersect_caps(&self, ray: Ray, shape: &Shape, xs: &mut Vec<Intersection>) {
+        if !self.closed || equal(ray.direction().get_y(), 0.0) {
+            return;
+        }
+
+        for y in [self.minimum, self.maximum] {
+            let t = (y - ray.origin().get_y()) / ray.direction().get_y();
+            if check_cap(ray, t) {
+                xs.push(Intersection::new(t, shape.clone()));
+            }
+        }
+    }
+
+    pub(super) fn set_transform(&mut self, m: Transform) {
+        self.matrix = m;
+    }
+    pub(super) fn transform(&self) -> &Transform {
+        &self.matrix
+    }
+    pub(super) fn bounds(&self) -> Bounds {
+        Bounds::new(
+            Tuple::point(-1, self.minimum, -1),
+            Tuple::point(1, self.maximum, 1),
+        )
+    }
+    pub(super) fn normal_at(&self, object_point: Tuple) -> Tuple {
+        let dist = object_point.get_x().powi(2) + object_point.get_z().powi(2);
+
+        if dist < 1.0 && object_point.get_y() >= self.maximum - EPSILON {
+            Tuple::vector(0, 1, 0)
+        } else if dist < 1.0 && object_point.get_y() <= self.minimum + EPSILON {
+            Tuple::vector(0, -1, 0)
+        } else {
+            Tuple::vector(object_point.get_x(), 0, object_point.get_z())
+        }
+    }
+    pub(super) fn material(&self) -> Material {
+        self.material
+    }
+    pub(super) fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+    pub(super) fn flags(&self) -> ShapeFlags {
+        self.flags
+    }
+    pub(super) fn set_flags(&mut self, flags: ShapeFlags) {
+        self.flags = flags;
+    }
+}
+
+fn check_cap(ray: Ray, t: Num) -> bool {
+    let x = ray.origin().get_x() + t * ray.direction().get_x();
+    let z = ray.origin().get_z() + t * ray.direction().get_z();
+    x.powi(2) + z.powi(2) <= 1.0
+}
+
+impl PartialEq for Cylinder {
+    fn eq(&self, other: &Self) -> bool {
+        self.transform() == other.transform()
+            && self.material() == other.material()
+            && self.flags() == other.flags()
+            && self.minimum == other.minimum
+            && self.maximum == other.maximum
+            && self.closed == other.closed
+    }
+}
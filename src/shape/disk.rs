@@ -0,0 +1,83 @@
+use super::{plane::xz_plane_hit, Bounds};
+use crate::{transformation::Transform, Intersection, Material, Num, Ray, Shape, ShapeFlags, Tuple};
+
+// Flat disk in the xz plane facing +y like `Plane`. A nonzero inner radius
+// cuts a hole in the middle, making an annulus.
+#[derive(Clone, Copy)]
+pub struct Disk {
+    matrix: Transform,
+    material: Material,
+    flags: ShapeFlags,
+    radius: Num,
+    inner_radius: Num,
+}
+
+impl Disk {
+    pub fn new(radius: Num) -> Disk {
+        Self::annulus(0.0, radius)
+    }
+    pub fn annulus(inner_radius: Num, radius: Num) -> Disk {
+        Self {
+            matrix: Transform::identity(),
+            material: Material::default(),
+            flags: ShapeFlags::default(),
+            radius,
+            inner_radius,
+        }
+    }
+
+    pub fn radius(&self) -> Num {
+        self.radius
+    }
+    pub fn inner_radius(&self) -> Num {
+        self.inner_radius
+    }
+
+    pub(super) fn intersect(&self, ray: Ray, shape: &Shape) -> Vec<Intersection> {
+        let t = match xz_plane_hit(ray) {
+            Some(t) => t,
+            None => return vec![],
+        };
+        let p = ray.position(t);
+        let dist2 = p.get_x() * p.get_x() + p.get_z() * p.get_z();
+        if dist2 > self.radius * self.radius || dist2 < self.inner_radius * self.inner_radius {
+            return vec![];
+        }
+        vec![Intersection::new(t, shape.clone())]
+    }
+
+    pub(super) fn set_transform(&mut self, m: Transform) {
+        self.matrix = m;
+    }
+    pub(super) fn transform(&self) -> &Transform {
+        &self.matrix
+    }
+    pub(super) fn bounds(&self) -> Bounds {
+        Bounds::new(Tuple::point(-self.radius, 0, -self.radius), Tuple::point(self.radius, 0, self.radius))
+    }
+    pub(super) fn normal_at(&self, _object_point: Tuple) -> Tuple {
+        Tuple::vector(0, 1, 0)
+    }
+    pub(super) fn material(&self) -> Material {
+        self.material
+    }
+    pub(super) fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+    pub(super) fn flags(&self) -> ShapeFlags {
+        self.flags
+    }
+    pub(super) fn set_flags(&mut self, flags: ShapeFlags) {
+        self.flags = flags;
+    }
+}
+
+impl PartialEq for Disk {
+    fn eq(&self, other: &Self) -> bool {
+        self.transform() == other.transform()
+            && self.material() == other.material()
+            && self.flags() == other.flags()
+            && self.radius == other.radius
+            && self.inner_radius == other.inner_radius
+    }
+}
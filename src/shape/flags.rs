@@ -0,0 +1,74 @@
+use crate::ray::RayKind;
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct ShapeFlags {
+    casts_shadow: bool,
+    receives_shadow: bool,
+    visible_to_camera: bool,
+    visible_to_reflection: bool,
+    visible_to_refraction: bool,
+}
+
+impl Default for ShapeFlags {
+    fn default() -> ShapeFlags {
+        ShapeFlags {
+            casts_shadow: true,
+            receives_shadow: true,
+            visible_to_camera: true,
+            visible_to_reflection: true,
+            visible_to_refraction: true,
+        }
+    }
+}
+
+impl ShapeFlags {
+    pub fn casts_shadow(&self) -> bool {
+        self.casts_shadow
+    }
+    pub fn receives_shadow(&self) -> bool {
+        self.receives_shadow
+    }
+    pub fn visible_to_camera(&self) -> bool {
+        self.visible_to_camera
+    }
+    pub fn visible_to_reflection(&self) -> bool {
+        self.visible_to_reflection
+    }
+    pub fn visible_to_refraction(&self) -> bool {
+        self.visible_to_refraction
+    }
+    // Both sets of flags must allow something for the result to allow it.
+    pub(crate) fn and(&self, other: ShapeFlags) -> ShapeFlags {
+        ShapeFlags {
+            casts_shadow: self.casts_shadow && other.casts_shadow,
+            receives_shadow: self.receives_shadow && other.receives_shadow,
+            visible_to_camera: self.visible_to_camera && other.visible_to_camera,
+            visible_to_reflection: self.visible_to_reflection && other.visible_to_reflection,
+            visible_to_refraction: self.visible_to_refraction && other.visible_to_refraction,
+        }
+    }
+    pub fn visible_to(&self, kind: RayKind) -> bool {
+        match kind {
+            RayKind::Camera => self.visible_to_camera,
+            RayKind::Reflection => self.visible_to_reflection,
+            RayKind::Refraction => self.visible_to_refraction,
+            RayKind::Shadow => self.casts_shadow,
+        }
+    }
+
+    pub fn set_casts_shadow(&mut self, val: bool) {
+        self.casts_shadow = val;
+    }
+    pub fn set_receives_shadow(&mut self, val: bool) {
+        self.receives_shadow = val;
+    }
+    pub fn set_visible_to_camera(&mut self, val: bool) {
+        self.visible_to_camera = val;
+    }
+    pub fn set_visible_to_reflection(&mut self, val: bool) {
+        self.visible_to_reflection = val;
+    }
+    pub fn set_visible_to_refraction(&mut self, val: bool) {
+        self.visible_to_refraction = val;
+    }
+}
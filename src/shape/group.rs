@@ -0,0 +1,101 @@
+use std::sync::{Arc, OnceLock};
+
+use super::Bounds;
+use crate::{bvh::Bvh, transformation::Transform, Intersection, Material, Ray, Shape, ShapeFlags};
+
+// Children are shared, so cloning a group (or a hit on one) never copies the
+// geometry underneath it. The BVH over them is built on first use.
+#[derive(Clone)]
+pub struct Group {
+    matrix: Transform,
+    // Set once a material is given to the group, and then handed to every
+    // child, including ones added afterwards.
+    material: Option<Material>,
+    flags: ShapeFlags,
+    children: Arc<Vec<Shape>>,
+    bvh: Arc<OnceLock<Bvh>>,
+}
+
+impl Group {
+    pub fn new(children: Vec<Shape>) -> Group {
+        Self {
+            matrix: Transform::identity(),
+            material: None,
+            flags: ShapeFlags::default(),
+            children: Arc::new(children),
+            bvh: Arc::default(),
+        }
+    }
+
+    pub fn children(&self) -> &[Shape] {
+        &self.children
+    }
+    pub fn add_child(&mut self, mut child: Shape) {
+        if let Some(material) = self.material {
+            child.set_material(material);
+        }
+        Arc::make_mut(&mut self.children).push(child);
+        self.bvh = Arc::default();
+    }
+
+    fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| Bvh::build(&self.children))
+    }
+
+    // Hits come back on the leaf shape with the group's transform folded into
+    // it, so normals and patterns on the hit object already see the whole
+    // parent chain.
+    pub(super) fn intersect(&self, ray: Ray) -> Vec<Intersection> {
+        let mut xs: Vec<Intersection> = self
+            .bvh()
+            .intersect(&self.children, ray)
+            .into_iter()
+            .map(|i| adopt(i, self.matrix, self.flags))
+            .collect();
+        xs.sort_by(|a, b| a.t().partial_cmp(&b.t()).unwrap());
+        xs
+    }
+
+    pub(super) fn bounds(&self) -> Bounds {
+        self.bvh().bounds()
+    }
+    pub(super) fn set_transform(&mut self, m: Transform) {
+        self.matrix = m;
+    }
+    pub(super) fn transform(&self) -> &Transform {
+        &self.matrix
+    }
+    pub(super) fn material(&self) -> Material {
+        self.material.unwrap_or_default()
+    }
+    // A material set on a group applies to everything inside it.
+    pub(super) fn set_material(&mut self, material: Material) {
+        self.material = Some(material);
+        for child in Arc::make_mut(&mut self.children).iter_mut() {
+            child.set_material(material);
+        }
+    }
+    pub(super) fn flags(&self) -> ShapeFlags {
+        self.flags
+    }
+    pub(super) fn set_flags(&mut self, flags: ShapeFlags) {
+        self.flags = flags;
+    }
+}
+
+// Folds a parent's transform and flags into the object of a child's hit.
+pub(super) fn adopt(i: Intersection, matrix: Transform, flags: ShapeFlags) -> Intersection {
+    let mut obj = i.object();
+    obj.set_cached_transform(matrix * *obj.cached_transform());
+    obj.set_flags(obj.flags().and(flags));
+    Intersection::with_uv(i.t(), obj, i.u(), i.v())
+}
+
+impl PartialEq for Group {
+    fn eq(&self, other: &Self) -> bool {
+        self.transform() == other.transform()
+            && self.material == other.material
+            && self.flags() == other.flags()
+            && self.children == other.children
+    }
+}
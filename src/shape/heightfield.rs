@@ -0,0 +1,203 @@
+use std::sync::Arc;
+
+use super::{triangle::moller_trumbore, Bounds};
+use crate::{transformation::Transform, Canvas, Intersection, Material, Matrix4x4, Num, Ray, Shape, ShapeFlags, Tuple};
+
+// Terrain over the unit square in xz, with `heights[row][column]` sampled on
+// an even grid: columns run along x and rows along z. Each grid cell is two
+// triangles, but rays only visit the cells under them.
+#[derive(Clone)]
+pub struct Heightfield {
+    matrix: Transform,
+    material: Material,
+    flags: ShapeFlags,
+    heights: Arc<Vec<Vec<Num>>>,
+    normals: Arc<Vec<Vec<Tuple>>>,
+    min_height: Num,
+    max_height: Num,
+}
+
+impl Heightfield {
+    pub fn new(heights: Vec<Vec<Num>>) -> Result<Heightfield, String> {
+        let columns = heights.first().map_or(0, |row| row.len());
+        if heights.len() < 2 || columns < 2 {
+            return Err("a heightfield needs at least 2x2 samples".to_string());
+        }
+        if let Some(row) = heights.iter().position(|row| row.len() != columns) {
+            return Err(format!("row {} has {} samples, expected {}", row, heights[row].len(), columns));
+        }
+        if heights.iter().flatten().any(|h| !h.is_finite()) {
+            return Err("heights must be finite".to_string());
+        }
+
+        let normals = vertex_normals(&heights);
+        let all = heights.iter().flatten();
+        let min_height = all.clone().copied().fold(Num::INFINITY, Num::min);
+        let max_height = all.copied().fold(Num::NEG_INFINITY, Num::max);
+        Ok(Self {
+            matrix: Transform::identity(),
+            material: Material::default(),
+            flags: ShapeFlags::default(),
+            heights: Arc::new(heights),
+            normals: Arc::new(normals),
+            min_height,
+            max_height,
+        })
+    }
+
+    // Heights from the brightness of each pixel, image rows running along z.
+    pub fn from_canvas(canvas: &Canvas) -> Result<Heightfield, String> {
+        let heights = (0..canvas.height())
+            .map(|y| {
+                (0..canvas.width())
+                    .map(|x| {
+                        let p = canvas.get(x, y);
+                        (p.r() + p.g() + p.b()) / 3.0
+                    })
+                    .collect()
+            })
+            .collect();
+        Heightfield::new(heights)
+    }
+
+    pub fn heights(&self) -> &[Vec<Num>] {
+        &self.heights
+    }
+    pub fn rows(&self) -> usize {
+        self.heights.len()
+    }
+    pub fn columns(&self) -> usize {
+        self.heights[0].len()
+    }
+
+    // Walks the cells under the ray with a grid DDA, in grid units where
+    // every cell is 1x1 (scaling the ray leaves t unchanged). Cells whose
+    // height range the ray passes over or under are skipped.
+    pub(super) fn intersect(&self, ray: Ray, shape: &Shape) -> Vec<Intersection> {
+        let (cells_x, cells_z) = ((self.columns() - 1) as Num, (self.rows() - 1) as Num);
+        let grid_ray = ray.transform(Matrix4x4::scaling(cells_x, 1, cells_z));
+        let grid_bounds = Bounds::new(
+            Tuple::point(0, self.min_height, 0),
+            Tuple::point(cells_x, self.max_height, cells_z),
+        );
+        let (start, end) = match grid_bounds.padded(1e-9).ray_span(grid_ray) {
+            Some(span) => span,
+            None => return vec![],
+        };
+
+        let (origin, direction) = (grid_ray.origin(), grid_ray.direction());
+        let entry = grid_ray.position(start);
+        let axis = |p: Num, d: Num, cells: Num| {
+            let cell = p.floor().clamp(0.0, cells - 1.0);
+            let (step, boundary) = if d > 0.0 { (1, cell + 1.0) } else { (-1, cell) };
+            let next = if d == 0.0 { Num::INFINITY } else { (boundary - p) / d + start };
+            (cell as isize, step, next, 1.0 / d.abs())
+        };
+        let (mut x, step_x, mut next_x, delta_x) = axis(entry.get_x(), direction.get_x(), cells_x);
+        let (mut z, step_z, mut next_z, delta_z) = axis(entry.get_z(), direction.get_z(), cells_z);
+
+        let mut xs: Vec<Intersection> = vec![];
+        let mut t = start;
+        while t <= end && x >= 0 && z >= 0 && x < cells_x as isize && z < cells_z as isize {
+            let exit = next_x.min(next_z).min(end);
+            let (y1, y2) = (origin.get_y() + direction.get_y() * t, origin.get_y() + direction.get_y() * exit);
+            let (low, high) = self.cell_range(x as usize, z as usize);
+            if y1.max(y2) >= low && y1.min(y2) <= high {
+                for (p1, p2, p3) in self.cell_triangles(x as usize, z as usize) {
+                    if let Some((hit, ..)) = moller_trumbore(p1, p2 - p1, p3 - p1, grid_ray) {
+                        xs.push(Intersection::new(hit, shape.clone()));
+                    }
+                }
+            }
+            if next_x < next_z {
+                x += step_x;
+                t = next_x;
+                next_x += delta_x;
+            } else {
+                z += step_z;
+                t = next_z;
+                next_z += delta_z;
+            }
+        }
+        // Edges shared by two triangles report the same crossing twice.
+        xs.sort_by(|a, b| a.t().partial_cmp(&b.t()).unwrap());
+        xs.dedup_by(|a, b| (a.t() - b.t()).abs() <= 1e-9);
+        xs
+    }
+
+    fn cell_range(&self, x: usize, z: usize) -> (Num, Num) {
+        let h = &self.heights;
+        let corners = [h[z][x], h[z][x + 1], h[z + 1][x], h[z + 1][x + 1]];
+        (corners.iter().copied().fold(Num::INFINITY, Num::min), corners.iter().copied().fold(Num::NEG_INFINITY, Num::max))
+    }
+
+    fn cell_triangles(&self, x: usize, z: usize) -> [(Tuple, Tuple, Tuple); 2] {
+        let corner = |dx: usize, dz: usize| Tuple::point((x + dx) as Num, self.heights[z + dz][x + dx], (z + dz) as Num);
+        let (p00, p10, p01, p11) = (corner(0, 0), corner(1, 0), corner(0, 1), corner(1, 1));
+        [(p00, p10, p11), (p00, p11, p01)]
+    }
+
+    pub(super) fn set_transform(&mut self, m: Transform) {
+        self.matrix = m;
+    }
+    pub(super) fn transform(&self) -> &Transform {
+        &self.matrix
+    }
+    pub(super) fn bounds(&self) -> Bounds {
+        Bounds::new(Tuple::point(0, self.min_height, 0), Tuple::point(1, self.max_height, 1))
+    }
+    // Vertex normals of the surrounding cell, blended bilinearly.
+    pub(super) fn normal_at(&self, object_point: Tuple) -> Tuple {
+        let (cells_x, cells_z) = ((self.columns() - 1) as Num, (self.rows() - 1) as Num);
+        let (gx, gz) = (object_point.get_x() * cells_x, object_point.get_z() * cells_z);
+        let x = gx.floor().clamp(0.0, cells_x - 1.0);
+        let z = gz.floor().clamp(0.0, cells_z - 1.0);
+        let (fx, fz) = ((gx - x).clamp(0.0, 1.0), (gz - z).clamp(0.0, 1.0));
+        let (x, z) = (x as usize, z as usize);
+        let n = &self.normals;
+        let near = n[z][x] * (1.0 - fx) + n[z][x + 1] * fx;
+        let far = n[z + 1][x] * (1.0 - fx) + n[z + 1][x + 1] * fx;
+        (near * (1.0 - fz) + far * fz).normalize()
+    }
+    pub(super) fn material(&self) -> Material {
+        self.material
+    }
+    pub(super) fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+    pub(super) fn flags(&self) -> ShapeFlags {
+        self.flags
+    }
+    pub(super) fn set_flags(&mut self, flags: ShapeFlags) {
+        self.flags = flags;
+    }
+}
+
+// Normals from the slope across each sample's neighbours, one-sided at the
+// edges, in the unit square's coordinates.
+fn vertex_normals(heights: &[Vec<Num>]) -> Vec<Vec<Tuple>> {
+    let (rows, columns) = (heights.len(), heights[0].len());
+    let slope = |a: Num, b: Num, samples: usize, steps: usize| (b - a) * (samples - 1) as Num / steps as Num;
+    (0..rows)
+        .map(|z| {
+            (0..columns)
+                .map(|x| {
+                    let (x0, x1) = (x.saturating_sub(1), (x + 1).min(columns - 1));
+                    let (z0, z1) = (z.saturating_sub(1), (z + 1).min(rows - 1));
+                    let dx = slope(heights[z][x0], heights[z][x1], columns, x1 - x0);
+                    let dz = slope(heights[z0][x], heights[z1][x], rows, z1 - z0);
+                    Tuple::vector(-dx, 1, -dz).normalize()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+impl PartialEq for Heightfield {
+    fn eq(&self, other: &Self) -> bool {
+        self.transform() == other.transform()
+            && self.material() == other.material()
+            && self.flags() == other.flags()
+            && self.heights == other.heights
+    }
+}
@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use super::{group::adopt, Bounds};
+use crate::{transformation::Transform, Intersection, Material, Ray, Shape, ShapeFlags};
+
+// Another placement of a shared shape, usually a whole group. Only the
+// reference is stored, so any number of instances cost one copy of the
+// geometry. Hits are adopted like a group's, with the material override
+// applied to the leaf that was hit.
+#[derive(Clone)]
+pub struct Instance {
+    matrix: Transform,
+    material: Option<Material>,
+    flags: ShapeFlags,
+    target: Arc<Shape>,
+}
+
+impl Instance {
+    pub fn new(target: Arc<Shape>) -> Instance {
+        Self {
+            matrix: Transform::identity(),
+            material: None,
+            flags: ShapeFlags::default(),
+            target,
+        }
+    }
+
+    pub fn target(&self) -> &Arc<Shape> {
+        &self.target
+    }
+    pub fn material_override(&self) -> Option<Material> {
+        self.material
+    }
+    pub fn clear_material_override(&mut self) {
+        self.material = None;
+    }
+
+    pub(super) fn intersect(&self, ray: Ray) -> Vec<Intersection> {
+        self.target
+            .intersect(ray)
+            .into_iter()
+            .map(|i| {
+                let i = adopt(i, self.matrix, self.flags);
+                match self.material {
+                    Some(material) => {
+                        let mut obj = i.object();
+                        obj.set_material(material);
+                        Intersection::with_uv(i.t(), obj, i.u(), i.v())
+                    }
+                    None => i,
+                }
+            })
+            .collect()
+    }
+
+    pub(super) fn bounds(&self) -> Bounds {
+        self.target.world_bounds()
+    }
+    pub(super) fn set_transform(&mut self, m: Transform) {
+        self.matrix = m;
+    }
+    pub(super) fn transform(&self) -> &Transform {
+        &self.matrix
+    }
+    // Without an override the instance looks like what it places.
+    pub(super) fn material(&self) -> Material {
+        self.material.unwrap_or_else(|| self.target.material())
+    }
+    pub(super) fn set_material(&mut self, material: Material) {
+        self.material = Some(material);
+    }
+    pub(super) fn flags(&self) -> ShapeFlags {
+        self.flags
+    }
+    pub(super) fn set_flags(&mut self, flags: ShapeFlags) {
+        self.flags = flags;
+    }
+}
+
+// Instances of the same shared shape are told apart by placement only.
+impl PartialEq for Instance {
+    fn eq(&self, other: &Self) -> bool {
+        self.transform() == other.transform()
+            && self.material == other.material
+            && self.flags() == other.flags()
+            && Arc::ptr_eq(&self.target, &other.target)
+    }
+}
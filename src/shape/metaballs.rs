@@ -0,0 +1,228 @@
+use std::sync::Arc;
+
+use super::Bounds;
+use crate::{roots, transformation::Transform, Intersection, Material, Num, Ray, Shape, ShapeFlags, Tuple};
+
+// How a ball's contribution falls off, as a polynomial in s = r² / radius²
+// that reaches zero with zero slope at the radius.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Falloff {
+    // (1 - s)²
+    Quartic,
+    // Wyvill's soft object field, 1 - 22/9 s + 17/9 s² - 4/9 s³.
+    Wyvill,
+}
+
+impl Falloff {
+    // Coefficients in s, lowest degree first.
+    fn coefficients(self) -> &'static [Num] {
+        match self {
+            Falloff::Quartic => &[1.0, -2.0, 1.0],
+            Falloff::Wyvill => &[1.0, -22.0 / 9.0, 17.0 / 9.0, -4.0 / 9.0],
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Metaball {
+    center: Tuple,
+    radius: Num,
+    weight: Num,
+}
+
+impl Metaball {
+    // A negative weight carves into the blobs around it.
+    pub fn new<T1, T2>(center: Tuple, radius: T1, weight: T2) -> Metaball
+    where
+        T1: Into<Num>,
+        T2: Into<Num>,
+    {
+        Metaball {
+            center,
+            radius: radius.into(),
+            weight: weight.into(),
+        }
+    }
+
+    pub fn center(&self) -> Tuple {
+        self.center
+    }
+    pub fn radius(&self) -> Num {
+        self.radius
+    }
+    pub fn weight(&self) -> Num {
+        self.weight
+    }
+}
+
+// Surface where the summed field of the balls equals the threshold.
+#[derive(Clone)]
+pub struct Metaballs {
+    matrix: Transform,
+    material: Material,
+    flags: ShapeFlags,
+    balls: Arc<Vec<Metaball>>,
+    threshold: Num,
+    falloff: Falloff,
+}
+
+impl Metaballs {
+    pub fn new(balls: Vec<Metaball>, threshold: Num, falloff: Falloff) -> Result<Metaballs, String> {
+        if !(threshold > 0.0 && threshold.is_finite()) {
+            return Err(format!("threshold must be positive, got {}", threshold));
+        }
+        if let Some(i) = balls.iter().position(|b| !(b.radius > 0.0 && b.radius.is_finite() && b.weight.is_finite())) {
+            return Err(format!("ball {} needs a positive radius and a finite weight", i));
+        }
+        Ok(Self {
+            matrix: Transform::identity(),
+            material: Material::default(),
+            flags: ShapeFlags::default(),
+            balls: Arc::new(balls),
+            threshold,
+            falloff,
+        })
+    }
+
+    pub fn balls(&self) -> &[Metaball] {
+        &self.balls
+    }
+    pub fn threshold(&self) -> Num {
+        self.threshold
+    }
+    pub fn falloff(&self) -> Falloff {
+        self.falloff
+    }
+
+    pub fn field_at(&self, object_point: Tuple) -> Num {
+        let coefficients = self.falloff.coefficients();
+        self.balls
+            .iter()
+            .map(|b| {
+                let s = (object_point - b.center).dot(&(object_point - b.center)) / (b.radius * b.radius);
+                if s >= 1.0 {
+                    0.0
+                } else {
+                    b.weight * coefficients.iter().rev().fold(0.0, |acc, c| acc * s + c)
+                }
+            })
+            .sum()
+    }
+
+    // Along the ray every ball adds a polynomial in t while the ray is inside
+    // it, so between the points where the ray enters or leaves a ball the
+    // field is one polynomial, solved exactly for the threshold.
+    pub(super) fn intersect(&self, ray: Ray, shape: &Shape) -> Vec<Intersection> {
+        let scale = ray.direction().magnitude();
+        let direction = ray.direction() / scale;
+        let origin = ray.origin();
+
+        let mut spans: Vec<(Num, Num, Vec<Num>)> = vec![];
+        for ball in self.balls.iter() {
+            let to_origin = origin - ball.center;
+            let b = direction.dot(&to_origin);
+            let c = to_origin.dot(&to_origin) - ball.radius * ball.radius;
+            let discriminant = b * b - c;
+            if discriminant <= 0.0 {
+                continue;
+            }
+            // s(t) = (t² + 2bt + |o - c|²) / radius², lowest degree first.
+            let r2 = ball.radius * ball.radius;
+            let s = [(c + r2) / r2, 2.0 * b / r2, 1.0 / r2];
+            let mut field = vec![0.0];
+            for coefficient in self.falloff.coefficients().iter().rev() {
+                field = multiply(&field, &s);
+                field[0] += coefficient;
+            }
+            let root = discriminant.sqrt();
+            spans.push((-b - root, -b + root, field.iter().map(|f| f * ball.weight).collect()));
+        }
+
+        let mut breaks: Vec<Num> = spans.iter().flat_map(|(t0, t1, _)| [*t0, *t1]).collect();
+        breaks.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let mut xs = vec![];
+        for pair in breaks.windows(2) {
+            let (lo, hi) = (pair[0], pair[1]);
+            if hi <= lo {
+                continue;
+            }
+            let mid = 0.5 * (lo + hi);
+            let mut sum = vec![-self.threshold];
+            for (_, _, field) in spans.iter().filter(|(t0, t1, _)| *t0 < mid && mid < *t1) {
+                sum.resize(sum.len().max(field.len()), 0.0);
+                sum.iter_mut().zip(field).for_each(|(a, b)| *a += b);
+            }
+            sum.reverse();
+            for t in roots::solve_polynomial(&sum) {
+                if lo <= t && t < hi {
+                    xs.push(Intersection::new(t / scale, shape.clone()));
+                }
+            }
+        }
+        xs
+    }
+
+    pub(super) fn set_transform(&mut self, m: Transform) {
+        self.matrix = m;
+    }
+    pub(super) fn transform(&self) -> &Transform {
+        &self.matrix
+    }
+    // Only balls adding to the field can push the surface out.
+    pub(super) fn bounds(&self) -> Bounds {
+        self.balls.iter().filter(|b| b.weight > 0.0).fold(Bounds::empty(), |bounds, b| {
+            let r = Tuple::vector(b.radius, b.radius, b.radius);
+            bounds.merge(&Bounds::new(b.center - r, b.center + r))
+        })
+    }
+    // The field falls off outwards, so the normal is against its gradient.
+    pub(super) fn normal_at(&self, object_point: Tuple) -> Tuple {
+        let coefficients = self.falloff.coefficients();
+        let mut gradient = Tuple::vector(0, 0, 0);
+        for b in self.balls.iter() {
+            let offset = object_point - b.center;
+            let r2 = b.radius * b.radius;
+            let s = offset.dot(&offset) / r2;
+            if s >= 1.0 {
+                continue;
+            }
+            let slope = coefficients.iter().enumerate().skip(1).rev().fold(0.0, |acc, (i, c)| acc * s + c * i as Num);
+            gradient = gradient + offset * (b.weight * slope * 2.0 / r2);
+        }
+        (-gradient).normalize()
+    }
+    pub(super) fn material(&self) -> Material {
+        self.material
+    }
+    pub(super) fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+    pub(super) fn flags(&self) -> ShapeFlags {
+        self.flags
+    }
+    pub(super) fn set_flags(&mut self, flags: ShapeFlags) {
+        self.flags = flags;
+    }
+}
+
+// Product of two polynomials, lowest degree first.
+fn multiply(a: &[Num], b: &[Num]) -> Vec<Num> {
+    let mut product = vec![0.0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            product[i + j] += x * y;
+        }
+    }
+    product
+}
+
+impl PartialEq for Metaballs {
+    fn eq(&self, other: &Self) -> bool {
+        self.transform() == other.transform()
+            && self.material() == other.material()
+            && self.flags() == other.flags()
+            && self.balls == other.balls
+            && self.threshold == other.threshold
+            && self.falloff == other.falloff
+    }
+}
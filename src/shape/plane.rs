@@ -1,32 +1,40 @@
-use crate::{Intersection, Material, Matrix4x4, Ray, Shape, Tuple, EPSILON};
+use super::Bounds;
+use crate::{transformation::Transform, Intersection, Material, Num, Ray, Shape, ShapeFlags, Tuple, EPSILON};
 
 #[derive(Clone, Copy)]
 pub struct Plane {
-    matrix: Matrix4x4,
+    matrix: Transform,
     material: Material,
+    flags: ShapeFlags,
 }
 
 impl Plane {
     pub fn new() -> Plane {
         Self {
-            matrix: Matrix4x4::identity(),
+            matrix: Transform::identity(),
             material: Material::default(),
+            flags: ShapeFlags::default(),
         }
     }
 
-    pub(super) fn intersect(&self, ray: Ray, shape: Shape) -> Vec<Intersection> {
-        if ray.direction().get_y().abs() < EPSILON {
-            return vec![];
+    pub(super) fn intersect(&self, ray: Ray, shape: &Shape) -> Vec<Intersection> {
+        match xz_plane_hit(ray) {
+            Some(t) => vec![Intersection::new(t, shape.clone())],
+            None => vec![],
         }
-        let t = -ray.origin().get_y() / ray.direction().get_y();
-        vec![Intersection::new(t, shape)]
     }
 
-    pub(super) fn set_transform(&mut self, m: Matrix4x4) {
+    pub(super) fn set_transform(&mut self, m: Transform) {
         self.matrix = m;
     }
-    pub(super) fn transform(&self) -> Matrix4x4 {
-        self.matrix
+    pub(super) fn transform(&self) -> &Transform {
+        &self.matrix
+    }
+    pub(super) fn bounds(&self) -> Bounds {
+        Bounds::new(
+            Tuple::point(Num::NEG_INFINITY, 0, Num::NEG_INFINITY),
+            Tuple::point(Num::INFINITY, 0, Num::INFINITY),
+        )
     }
     pub(super) fn normal_at(&self, _object_point: Tuple) -> Tuple {
         Tuple::vector(0, 1, 0)
@@ -37,10 +45,26 @@ impl Plane {
     pub(super) fn set_material(&mut self, material: Material) {
         self.material = material;
     }
+    pub(super) fn flags(&self) -> ShapeFlags {
+        self.flags
+    }
+    pub(super) fn set_flags(&mut self, flags: ShapeFlags) {
+        self.flags = flags;
+    }
+}
+
+// Where the ray crosses y = 0, shared with the finite shapes lying in it.
+pub(super) fn xz_plane_hit(ray: Ray) -> Option<Num> {
+    if ray.direction().get_y().abs() < EPSILON {
+        return None;
+    }
+    Some(-ray.origin().get_y() / ray.direction().get_y())
 }
 
 impl PartialEq for Plane {
     fn eq(&self, other: &Self) -> bool {
-        self.transform() == other.transform() && self.material() == other.material()
+        self.transform() == other.transform()
+            && self.material() == other.material()
+            && self.flags() == other.flags()
     }
 }
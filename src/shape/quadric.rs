@@ -0,0 +1,165 @@
+use super::Bounds;
+use crate::{roots, transformation::Transform, Intersection, Material, Matrix4x4, Num, Ray, Shape, ShapeFlags, Tuple};
+
+// Surface where pᵀ Q p = 0 for homogeneous points p = (x, y, z, 1), negative
+// inside. Only the symmetric part of Q matters, so that is what is kept.
+// Unbounded quadrics can be cut down to a clip box.
+#[derive(Clone, Copy)]
+pub struct Quadric {
+    matrix: Transform,
+    material: Material,
+    flags: ShapeFlags,
+    coefficients: Matrix4x4,
+    clip: Option<Bounds>,
+}
+
+impl Quadric {
+    pub fn new(coefficients: Matrix4x4) -> Quadric {
+        let mut symmetric = coefficients;
+        for row in 0..4 {
+            for col in 0..4 {
+                symmetric.set(row, col, 0.5 * (coefficients.at(row, col) + coefficients.at(col, row)));
+            }
+        }
+        Self {
+            matrix: Transform::identity(),
+            material: Material::default(),
+            flags: ShapeFlags::default(),
+            coefficients: symmetric,
+            clip: None,
+        }
+    }
+
+    // x²/a² + y²/b² + z²/c² = 1
+    pub fn ellipsoid<T1, T2, T3>(a: T1, b: T2, c: T3) -> Quadric
+    where
+        T1: Into<Num>,
+        T2: Into<Num>,
+        T3: Into<Num>,
+    {
+        let (a, b, c) = (a.into(), b.into(), c.into());
+        Quadric::new(Matrix4x4::new([
+            1.0 / (a * a), 0.0, 0.0, 0.0,
+            0.0, 1.0 / (b * b), 0.0, 0.0,
+            0.0, 0.0, 1.0 / (c * c), 0.0,
+            0.0, 0.0, 0.0, -1.0,
+        ]))
+    }
+    // y = x² + z², opening upwards.
+    pub fn paraboloid() -> Quadric {
+        Quadric::new(Matrix4x4::new([
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, -0.5,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, -0.5, 0.0, 0.0,
+        ]))
+    }
+    // x² + z² - y² = 1 for one sheet around the y axis, or -1 for two sheets
+    // opening along it.
+    pub fn hyperboloid(one_sheet: bool) -> Quadric {
+        let constant = if one_sheet { -1.0 } else { 1.0 };
+        Quadric::new(Matrix4x4::new([
+            1.0, 0.0, 0.0, 0.0,
+            0.0, -1.0, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, constant,
+        ]))
+    }
+
+    // Keeps only the part of the surface inside `bounds`.
+    pub fn clipped(mut self, bounds: Bounds) -> Quadric {
+        self.clip = Some(bounds);
+        self
+    }
+
+    pub fn coefficients(&self) -> Matrix4x4 {
+        self.coefficients
+    }
+    pub fn clip(&self) -> Option<Bounds> {
+        self.clip
+    }
+
+    pub(super) fn intersect(&self, ray: Ray, shape: &Shape) -> Vec<Intersection> {
+        let (o, d) = (ray.origin(), ray.direction());
+        let (qo, qd) = (self.coefficients * o, self.coefficients * d);
+        let (a, b, c) = (d.dot(&qd), 2.0 * d.dot(&qo), o.dot(&qo));
+
+        roots::solve_polynomial(&[a, b, c])
+            .into_iter()
+            .filter(|t| self.clip.is_none_or(|clip| clip.contains_point(ray.position(*t))))
+            .map(|t| Intersection::new(t, shape.clone()))
+            .collect()
+    }
+
+    pub(super) fn set_transform(&mut self, m: Transform) {
+        self.matrix = m;
+    }
+    pub(super) fn transform(&self) -> &Transform {
+        &self.matrix
+    }
+    pub(super) fn bounds(&self) -> Bounds {
+        let surface = self.surface_bounds();
+        match self.clip {
+            Some(clip) => surface.overlap(&clip),
+            None => surface,
+        }
+    }
+    // Only ellipsoids are closed: their quadratic part is definite. The
+    // planes x = k touching one satisfy πᵀ Q⁻¹ π = 0 for π = (1, 0, 0, -k),
+    // a quadratic in k, and likewise along y and z. The adjugate is Q⁻¹
+    // times det Q, so it gives the same roots without dividing by a
+    // determinant that shrinks quickly with the quadric's size.
+    fn surface_bounds(&self) -> Bounds {
+        let q = self.coefficients;
+        let minors = [
+            q.at(0, 0),
+            q.at(0, 0) * q.at(1, 1) - q.at(0, 1) * q.at(1, 0),
+            q.submatrix(3, 3).determinant(),
+        ];
+        let definite = minors.iter().all(|m| *m > 0.0) || (minors[0] < 0.0 && minors[1] > 0.0 && minors[2] < 0.0);
+        let det = q.determinant();
+        if !definite || det == 0.0 || !det.is_finite() {
+            return Bounds::infinite();
+        }
+        let adjugate = |row, col| q.cofactor(col, row);
+
+        let mut extents = [(0.0, 0.0); 3];
+        for (axis, extent) in extents.iter_mut().enumerate() {
+            let ks = roots::solve_polynomial(&[adjugate(3, 3), -2.0 * adjugate(axis, 3), adjugate(axis, axis)]);
+            match ks[..] {
+                [low, high] => *extent = (low, high),
+                // No real points at all.
+                _ => return Bounds::empty(),
+            }
+        }
+        let [x, y, z] = extents;
+        Bounds::new(Tuple::point(x.0, y.0, z.0), Tuple::point(x.1, y.1, z.1))
+    }
+    // Gradient of pᵀ Q p, which is 2 Q p.
+    pub(super) fn normal_at(&self, object_point: Tuple) -> Tuple {
+        let g = self.coefficients * object_point;
+        Tuple::vector(g.get_x(), g.get_y(), g.get_z()).normalize()
+    }
+    pub(super) fn material(&self) -> Material {
+        self.material
+    }
+    pub(super) fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+    pub(super) fn flags(&self) -> ShapeFlags {
+        self.flags
+    }
+    pub(super) fn set_flags(&mut self, flags: ShapeFlags) {
+        self.flags = flags;
+    }
+}
+
+impl PartialEq for Quadric {
+    fn eq(&self, other: &Self) -> bool {
+        self.transform() == other.transform()
+            && self.material() == other.material()
+            && self.flags() == other.flags()
+            && self.coefficients == other.coefficients
+            && self.clip == other.clip
+    }
+}
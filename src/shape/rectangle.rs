@@ -0,0 +1,81 @@
+use super::{plane::xz_plane_hit, Bounds};
+use crate::{transformation::Transform, Intersection, Material, Num, Ray, Shape, ShapeFlags, Tuple};
+
+// Rectangle in the xz plane centred on the origin, facing +y like `Plane`.
+#[derive(Clone, Copy)]
+pub struct Rectangle {
+    matrix: Transform,
+    material: Material,
+    flags: ShapeFlags,
+    width: Num,
+    depth: Num,
+}
+
+impl Rectangle {
+    pub fn new(width: Num, depth: Num) -> Rectangle {
+        Self {
+            matrix: Transform::identity(),
+            material: Material::default(),
+            flags: ShapeFlags::default(),
+            width,
+            depth,
+        }
+    }
+
+    // Extent along x.
+    pub fn width(&self) -> Num {
+        self.width
+    }
+    // Extent along z.
+    pub fn depth(&self) -> Num {
+        self.depth
+    }
+
+    pub(super) fn intersect(&self, ray: Ray, shape: &Shape) -> Vec<Intersection> {
+        let t = match xz_plane_hit(ray) {
+            Some(t) => t,
+            None => return vec![],
+        };
+        let p = ray.position(t);
+        if p.get_x().abs() > self.width / 2.0 || p.get_z().abs() > self.depth / 2.0 {
+            return vec![];
+        }
+        vec![Intersection::new(t, shape.clone())]
+    }
+
+    pub(super) fn set_transform(&mut self, m: Transform) {
+        self.matrix = m;
+    }
+    pub(super) fn transform(&self) -> &Transform {
+        &self.matrix
+    }
+    pub(super) fn bounds(&self) -> Bounds {
+        let (x, z) = (self.width / 2.0, self.depth / 2.0);
+        Bounds::new(Tuple::point(-x, 0, -z), Tuple::point(x, 0, z))
+    }
+    pub(super) fn normal_at(&self, _object_point: Tuple) -> Tuple {
+        Tuple::vector(0, 1, 0)
+    }
+    pub(super) fn material(&self) -> Material {
+        self.material
+    }
+    pub(super) fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+    pub(super) fn flags(&self) -> ShapeFlags {
+        self.flags
+    }
+    pub(super) fn set_flags(&mut self, flags: ShapeFlags) {
+        self.flags = flags;
+    }
+}
+
+impl PartialEq for Rectangle {
+    fn eq(&self, other: &Self) -> bool {
+        self.transform() == other.transform()
+            && self.material() == other.material()
+            && self.flags() == other.flags()
+            && self.width == other.width
+            && self.depth == other.depth
+    }
+}
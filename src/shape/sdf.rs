@@ -0,0 +1,317 @@
+use std::sync::Arc;
+
+use super::Bounds;
+use crate::{transformation::Transform, Intersection, Material, Num, Ray, Shape, ShapeFlags, Tuple};
+
+// Step budget for a ray with no finite span through the bounds. Finite
+// spans get as many steps as MIN_STEP needs to cover them.
+const MAX_STEPS: usize = 1000;
+// Smallest march step, so a ray creeping up on a surface still crosses it.
+const MIN_STEP: Num = 0.0005;
+const GRADIENT_STEP: Num = 0.00001;
+
+// Signed distance function built from primitives and operations, negative
+// inside. Built with the constructors and chained operations, e.g.
+// `Sdf::sphere(1).smooth_union(Sdf::cuboid(1, 0.2, 1), 0.3)`.
+#[derive(Clone, PartialEq)]
+pub struct Sdf {
+    node: Arc<Node>,
+}
+
+#[derive(PartialEq)]
+enum Node {
+    Sphere(Num),
+    Cuboid(Tuple),
+    Torus(Num, Num),
+    Cylinder(Num, Num),
+    Union(Sdf, Sdf),
+    Intersection(Sdf, Sdf),
+    Difference(Sdf, Sdf),
+    SmoothUnion(Sdf, Sdf, Num),
+    Rounded(Sdf, Num),
+    // Rate in radians per unit of y, and the factor the warped distance is
+    // divided by to stay a safe step.
+    Twisted(Sdf, Num, Num),
+    Repeated(Sdf, Tuple, [Num; 3]),
+    Translated(Sdf, Tuple),
+}
+
+impl Sdf {
+    fn new(node: Node) -> Sdf {
+        Sdf { node: Arc::new(node) }
+    }
+
+    pub fn sphere<T>(radius: T) -> Sdf
+    where
+        T: Into<Num>,
+    {
+        Sdf::new(Node::Sphere(radius.into()))
+    }
+    // Box from -size to +size along each axis.
+    pub fn cuboid<T1, T2, T3>(x: T1, y: T2, z: T3) -> Sdf
+    where
+        T1: Into<Num>,
+        T2: Into<Num>,
+        T3: Into<Num>,
+    {
+        Sdf::new(Node::Cuboid(Tuple::vector(x, y, z)))
+    }
+    // Ring around the y axis, like `Torus`.
+    pub fn torus<T1, T2>(major_radius: T1, minor_radius: T2) -> Sdf
+    where
+        T1: Into<Num>,
+        T2: Into<Num>,
+    {
+        Sdf::new(Node::Torus(major_radius.into(), minor_radius.into()))
+    }
+    // Capped cylinder around the y axis from -half_height to +half_height.
+    pub fn cylinder<T1, T2>(radius: T1, half_height: T2) -> Sdf
+    where
+        T1: Into<Num>,
+        T2: Into<Num>,
+    {
+        Sdf::new(Node::Cylinder(radius.into(), half_height.into()))
+    }
+
+    pub fn union(self, other: Sdf) -> Sdf {
+        Sdf::new(Node::Union(self, other))
+    }
+    pub fn intersection(self, other: Sdf) -> Sdf {
+        Sdf::new(Node::Intersection(self, other))
+    }
+    pub fn difference(self, other: Sdf) -> Sdf {
+        Sdf::new(Node::Difference(self, other))
+    }
+    // Union blending the two surfaces together over a distance of about `k`.
+    pub fn smooth_union<T>(self, other: Sdf, k: T) -> Sdf
+    where
+        T: Into<Num>,
+    {
+        Sdf::new(Node::SmoothUnion(self, other, k.into()))
+    }
+    // Grows the surface outwards by `radius`, rounding off edges.
+    pub fn rounded<T>(self, radius: T) -> Sdf
+    where
+        T: Into<Num>,
+    {
+        Sdf::new(Node::Rounded(self, radius.into()))
+    }
+    // Rotates each slice around the y axis by `rate` radians per unit of y.
+    pub fn twisted<T>(self, rate: T) -> Sdf
+    where
+        T: Into<Num>,
+    {
+        let rate = rate.into();
+        let lipschitz = (1.0 + (rate * xz_reach(&self.bounds())).powi(2)).sqrt();
+        Sdf::new(Node::Twisted(self, rate, lipschitz))
+    }
+    // Copies spaced by `spacing`, `count` more on each side along every axis
+    // with a nonzero spacing.
+    pub fn repeated(self, spacing: Tuple, count: [u32; 3]) -> Sdf {
+        Sdf::new(Node::Repeated(self, spacing, count.map(|c| c as Num)))
+    }
+    pub fn translated<T1, T2, T3>(self, x: T1, y: T2, z: T3) -> Sdf
+    where
+        T1: Into<Num>,
+        T2: Into<Num>,
+        T3: Into<Num>,
+    {
+        Sdf::new(Node::Translated(self, Tuple::vector(x, y, z)))
+    }
+
+    pub fn distance(&self, p: Tuple) -> Num {
+        let (x, y, z) = (p.get_x(), p.get_y(), p.get_z());
+        match &*self.node {
+            Node::Sphere(r) => (x * x + y * y + z * z).sqrt() - r,
+            Node::Cuboid(size) => {
+                let q = [x.abs() - size.get_x(), y.abs() - size.get_y(), z.abs() - size.get_z()];
+                let outside = q.iter().map(|v| v.max(0.0).powi(2)).sum::<Num>().sqrt();
+                outside + q[0].max(q[1]).max(q[2]).min(0.0)
+            }
+            Node::Torus(major, minor) => {
+                let qx = (x * x + z * z).sqrt() - major;
+                (qx * qx + y * y).sqrt() - minor
+            }
+            Node::Cylinder(r, h) => {
+                let (dx, dy) = ((x * x + z * z).sqrt() - r, y.abs() - h);
+                dx.max(dy).min(0.0) + (dx.max(0.0).powi(2) + dy.max(0.0).powi(2)).sqrt()
+            }
+            Node::Union(a, b) => a.distance(p).min(b.distance(p)),
+            Node::Intersection(a, b) => a.distance(p).max(b.distance(p)),
+            Node::Difference(a, b) => a.distance(p).max(-b.distance(p)),
+            Node::SmoothUnion(a, b, k) => {
+                let (da, db) = (a.distance(p), b.distance(p));
+                if *k <= 0.0 {
+                    return da.min(db);
+                }
+                let h = (0.5 + 0.5 * (db - da) / k).clamp(0.0, 1.0);
+                db + (da - db) * h - k * h * (1.0 - h)
+            }
+            Node::Rounded(a, r) => a.distance(p) - r,
+            Node::Twisted(a, rate, lipschitz) => {
+                let (s, c) = (rate * y).sin_cos();
+                a.distance(Tuple::point(c * x - s * z, y, s * x + c * z)) / lipschitz
+            }
+            Node::Repeated(a, spacing, count) => {
+                let fold = |v: Num, s: Num, n: Num| if s == 0.0 { v } else { v - s * (v / s).round().clamp(-n, n) };
+                a.distance(Tuple::point(
+                    fold(x, spacing.get_x(), count[0]),
+                    fold(y, spacing.get_y(), count[1]),
+                    fold(z, spacing.get_z(), count[2]),
+                ))
+            }
+            Node::Translated(a, offset) => a.distance(p - *offset),
+        }
+    }
+
+    // Box the surface stays inside, used to limit marching.
+    pub fn bounds(&self) -> Bounds {
+        let symmetric = |x: Num, y: Num, z: Num| Bounds::new(Tuple::point(-x, -y, -z), Tuple::point(x, y, z));
+        match &*self.node {
+            Node::Sphere(r) => symmetric(*r, *r, *r),
+            Node::Cuboid(size) => symmetric(size.get_x(), size.get_y(), size.get_z()),
+            Node::Torus(major, minor) => symmetric(major + minor, *minor, major + minor),
+            Node::Cylinder(r, h) => symmetric(*r, *h, *r),
+            Node::Union(a, b) => a.bounds().merge(&b.bounds()),
+            // Neither keeps anything outside the first operand.
+            Node::Intersection(a, _) | Node::Difference(a, _) => a.bounds(),
+            // Blending pulls the surface out by at most k / 4.
+            Node::SmoothUnion(a, b, k) => a.bounds().merge(&b.bounds()).padded(k.max(0.0) / 4.0),
+            Node::Rounded(a, r) => a.bounds().padded(*r),
+            Node::Twisted(a, ..) => {
+                let b = a.bounds();
+                let reach = xz_reach(&b);
+                Bounds::new(Tuple::point(-reach, b.min().get_y(), -reach), Tuple::point(reach, b.max().get_y(), reach))
+            }
+            Node::Repeated(a, spacing, count) => {
+                let b = a.bounds();
+                let reach = Tuple::vector(
+                    spacing.get_x().abs() * count[0],
+                    spacing.get_y().abs() * count[1],
+                    spacing.get_z().abs() * count[2],
+                );
+                Bounds::new(b.min() - reach, b.max() + reach)
+            }
+            Node::Translated(a, offset) => {
+                let b = a.bounds();
+                Bounds::new(b.min() + *offset, b.max() + *offset)
+            }
+        }
+    }
+}
+
+// Furthest the box reaches from the y axis, which twisting sweeps around.
+fn xz_reach(b: &Bounds) -> Num {
+    let x = b.min().get_x().abs().max(b.max().get_x().abs());
+    let z = b.min().get_z().abs().max(b.max().get_z().abs());
+    (x * x + z * z).sqrt()
+}
+
+// Shape surrounding the zero level of an `Sdf`, found by sphere tracing.
+#[derive(Clone)]
+pub struct SdfShape {
+    matrix: Transform,
+    material: Material,
+    flags: ShapeFlags,
+    sdf: Sdf,
+    bounds: Bounds,
+}
+
+impl SdfShape {
+    pub fn new(sdf: Sdf) -> SdfShape {
+        Self {
+            matrix: Transform::identity(),
+            material: Material::default(),
+            flags: ShapeFlags::default(),
+            bounds: sdf.bounds(),
+            sdf,
+        }
+    }
+
+    pub fn sdf(&self) -> &Sdf {
+        &self.sdf
+    }
+
+    // Marches the whole stretch of the ray's line inside the bounds, so every
+    // crossing is reported (refraction and CSG need the far ones too). Steps
+    // are the distance to the surface, and each sign change of the distance
+    // is narrowed down by bisection.
+    pub(super) fn intersect(&self, ray: Ray, shape: &Shape) -> Vec<Intersection> {
+        let (start, end) = match self.bounds.padded(MIN_STEP * 4.0).ray_span(ray) {
+            Some(span) => span,
+            None => return vec![],
+        };
+        let speed = ray.direction().magnitude();
+        let distance_at = |t: Num| self.sdf.distance(ray.position(t));
+
+        let budget = ((end - start) * speed / MIN_STEP).ceil();
+        let steps = if budget.is_finite() { MAX_STEPS.max(budget as usize + 1) } else { MAX_STEPS };
+
+        let mut xs = vec![];
+        let (mut t, mut d) = (start, distance_at(start));
+        for _ in 0..steps {
+            if t >= end {
+                break;
+            }
+            let next_t = (t + d.abs().max(MIN_STEP) / speed).min(end);
+            let next_d = distance_at(next_t);
+            if (d < 0.0) != (next_d < 0.0) {
+                let (mut lo, mut hi) = (t, next_t);
+                for _ in 0..40 {
+                    let mid = 0.5 * (lo + hi);
+                    if (distance_at(mid) < 0.0) == (d < 0.0) {
+                        lo = mid;
+                    } else {
+                        hi = mid;
+                    }
+                }
+                xs.push(Intersection::new(0.5 * (lo + hi), shape.clone()));
+            }
+            t = next_t;
+            d = next_d;
+        }
+        xs
+    }
+
+    pub(super) fn set_transform(&mut self, m: Transform) {
+        self.matrix = m;
+    }
+    pub(super) fn transform(&self) -> &Transform {
+        &self.matrix
+    }
+    pub(super) fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+    // Gradient of the distance by central differences.
+    pub(super) fn normal_at(&self, object_point: Tuple) -> Tuple {
+        let h = GRADIENT_STEP;
+        let d = |dx: Num, dy: Num, dz: Num| self.sdf.distance(object_point + Tuple::vector(dx, dy, dz));
+        Tuple::vector(
+            d(h, 0.0, 0.0) - d(-h, 0.0, 0.0),
+            d(0.0, h, 0.0) - d(0.0, -h, 0.0),
+            d(0.0, 0.0, h) - d(0.0, 0.0, -h),
+        )
+        .normalize()
+    }
+    pub(super) fn material(&self) -> Material {
+        self.material
+    }
+    pub(super) fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+    pub(super) fn flags(&self) -> ShapeFlags {
+        self.flags
+    }
+    pub(super) fn set_flags(&mut self, flags: ShapeFlags) {
+        self.flags = flags;
+    }
+}
+
+impl PartialEq for SdfShape {
+    fn eq(&self, other: &Self) -> bool {
+        self.transform() == other.transform()
+            && self.material() == other.material()
+            && self.flags() == other.flags()
+            && self.sdf == other.sdf
+    }
+}
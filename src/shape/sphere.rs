@@ -1,21 +1,24 @@
-use crate::{Intersection, Material, Matrix4x4, Ray, Shape, Tuple};
+use super::Bounds;
+use crate::{transformation::Transform, Intersection, Material, Ray, Shape, ShapeFlags, Tuple};
 
 #[derive(Clone, Copy)]
 //Spehere
 pub struct Sphere {
-    matrix: Matrix4x4,
+    matrix: Transform,
     material: Material,
+    flags: ShapeFlags,
 }
 
 impl Sphere {
     pub fn new() -> Sphere {
         Self {
-            matrix: Matrix4x4::identity(),
+            matrix: Transform::identity(),
             material: Material::default(),
+            flags: ShapeFlags::default(),
         }
     }
 
-    pub(super) fn intersect(&self, ray: Ray, shape: Shape) -> Vec<Intersection> {
+    pub(super) fn intersect(&self, ray: Ray, shape: &Shape) -> Vec<Intersection> {
         let sphere_to_ray = ray.origin() - Tuple::point(0, 0, 0);
         let dir = ray.direction();
 
@@ -32,16 +35,22 @@ impl Sphere {
         let t1 = (-b - discriminant.sqrt()) / (2.0 * a);
         let t2 = (-b + discriminant.sqrt()) / (2.0 * a);
 
-        let mut v = Intersection::make(Intersection::new(t1, shape), Intersection::new(t2, shape));
+        let mut v = Intersection::make(
+            Intersection::new(t1, shape.clone()),
+            Intersection::new(t2, shape.clone()),
+        );
         v.sort_by(|a, b| a.t().partial_cmp(&b.t()).unwrap());
         v
     }
 
-    pub(super) fn set_transform(&mut self, m: Matrix4x4) {
+    pub(super) fn set_transform(&mut self, m: Transform) {
         self.matrix = m;
     }
-    pub(super) fn transform(&self) -> Matrix4x4 {
-        self.matrix
+    pub(super) fn transform(&self) -> &Transform {
+        &self.matrix
+    }
+    pub(super) fn bounds(&self) -> Bounds {
+        Bounds::new(Tuple::point(-1, -1, -1), Tuple::point(1, 1, 1))
     }
     pub(super) fn normal_at(&self, object_point: Tuple) -> Tuple {
         object_point - Tuple::point(0, 0, 0)
@@ -52,10 +61,18 @@ impl Sphere {
     pub(super) fn set_material(&mut self, material: Material) {
         self.material = material;
     }
+    pub(super) fn flags(&self) -> ShapeFlags {
+        self.flags
+    }
+    pub(super) fn set_flags(&mut self, flags: ShapeFlags) {
+        self.flags = flags;
+    }
 }
 
 impl PartialEq for Sphere {
     fn eq(&self, other: &Self) -> bool {
-        self.transform() == other.transform() && self.material() == other.material()
+        self.transform() == other.transform()
+            && self.material() == other.material()
+            && self.flags() == other.flags()
     }
 }
@@ -1,10 +1,12 @@
-use crate::{Intersection, Material, Matrix4x4, Ray, Shape, Tuple};
+use super::Bounds;
+use crate::{transformation::Transform, Intersection, Material, Ray, Shape, ShapeFlags, Tuple};
 
 #[derive(Clone, Copy)]
 //Spehere
 pub struct TestShape {
-    matrix: Matrix4x4,
+    matrix: Transform,
     material: Material,
+    flags: ShapeFlags,
 }
 
 pub(crate) static mut SAVED_RAY: Option<Ray> = None;
@@ -12,12 +14,13 @@ pub(crate) static mut SAVED_RAY: Option<Ray> = None;
 impl TestShape {
     pub fn new() -> TestShape {
         Self {
-            matrix: Matrix4x4::identity(),
+            matrix: Transform::identity(),
             material: Material::default(),
+            flags: ShapeFlags::default(),
         }
     }
 
-    pub(super) fn intersect(&self, ray: Ray, _shape: Shape) -> Vec<Intersection> {
+    pub(super) fn intersect(&self, ray: Ray, _shape: &Shape) -> Vec<Intersection> {
         unsafe {
             SAVED_RAY = Some(ray);
         }
@@ -25,11 +28,14 @@ impl TestShape {
         vec![]
     }
 
-    pub(super) fn set_transform(&mut self, m: Matrix4x4) {
+    pub(super) fn set_transform(&mut self, m: Transform) {
         self.matrix = m;
     }
-    pub(super) fn transform(&self) -> Matrix4x4 {
-        self.matrix
+    pub(super) fn transform(&self) -> &Transform {
+        &self.matrix
+    }
+    pub(super) fn bounds(&self) -> Bounds {
+        Bounds::new(Tuple::point(-1, -1, -1), Tuple::point(1, 1, 1))
     }
     pub(super) fn normal_at(&self, object_point: Tuple) -> Tuple {
         Tuple::vector(
@@ -44,10 +50,18 @@ impl TestShape {
     pub(super) fn set_material(&mut self, material: Material) {
         self.material = material;
     }
+    pub(super) fn flags(&self) -> ShapeFlags {
+        self.flags
+    }
+    pub(super) fn set_flags(&mut self, flags: ShapeFlags) {
+        self.flags = flags;
+    }
 }
 
 impl PartialEq for TestShape {
     fn eq(&self, other: &Self) -> bool {
-        self.transform() == other.transform() && self.material() == other.material()
+        self.transform() == other.transform()
+            && self.material() == other.material()
+            && self.flags() == other.flags()
     }
 }
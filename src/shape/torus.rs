@@ -0,0 +1,110 @@
+use super::Bounds;
+use crate::{roots, transformation::Transform, Intersection, Material, Num, Ray, Shape, ShapeFlags, Tuple};
+
+// Ring around the y axis: the tube of radius `minor` follows a circle of
+// radius `major` in the xz plane.
+#[derive(Clone, Copy)]
+pub struct Torus {
+    matrix: Transform,
+    material: Material,
+    flags: ShapeFlags,
+    major: Num,
+    minor: Num,
+}
+
+impl Torus {
+    pub fn new(major: Num, minor: Num) -> Torus {
+        Self {
+            matrix: Transform::identity(),
+            material: Material::default(),
+            flags: ShapeFlags::default(),
+            major,
+            minor,
+        }
+    }
+
+    pub fn major_radius(&self) -> Num {
+        self.major
+    }
+    pub fn minor_radius(&self) -> Num {
+        self.minor
+    }
+
+    pub(super) fn intersect(&self, ray: Ray, shape: &Shape) -> Vec<Intersection> {
+        // The quartic is solved along a unit direction starting at the point
+        // closest to the centre, which keeps its coefficients small.
+        let scale = ray.direction().magnitude();
+        let d = ray.direction() * (1.0 / scale);
+        let to_center = Tuple::point(0, 0, 0) - ray.origin();
+        let shift = to_center.dot(&d);
+        let o = ray.origin() + d * shift;
+
+        let outer = self.major + self.minor;
+        let o_vec = o - Tuple::point(0, 0, 0);
+        if o_vec.dot(&o_vec) > outer * outer {
+            return vec![];
+        }
+
+        let (r2, big_r2) = (self.minor * self.minor, self.major * self.major);
+        let od = o_vec.dot(&d);
+        let k = o_vec.dot(&o_vec) + big_r2 - r2;
+        let dxz = d.get_x() * d.get_x() + d.get_z() * d.get_z();
+        let oxz = o.get_x() * d.get_x() + o.get_z() * d.get_z();
+        let oo_xz = o.get_x() * o.get_x() + o.get_z() * o.get_z();
+
+        roots::solve_quartic(
+            1.0,
+            4.0 * od,
+            4.0 * od * od + 2.0 * k - 4.0 * big_r2 * dxz,
+            4.0 * od * k - 8.0 * big_r2 * oxz,
+            k * k - 4.0 * big_r2 * oo_xz,
+        )
+        .into_iter()
+        .map(|t| Intersection::new((t + shift) / scale, shape.clone()))
+        .collect()
+    }
+
+    pub(super) fn set_transform(&mut self, m: Transform) {
+        self.matrix = m;
+    }
+    pub(super) fn transform(&self) -> &Transform {
+        &self.matrix
+    }
+    pub(super) fn bounds(&self) -> Bounds {
+        let outer = self.major + self.minor;
+        Bounds::new(Tuple::point(-outer, -self.minor, -outer), Tuple::point(outer, self.minor, outer))
+    }
+    // Points away from the nearest point on the centre circle.
+    pub(super) fn normal_at(&self, object_point: Tuple) -> Tuple {
+        let (x, z) = (object_point.get_x(), object_point.get_z());
+        let len = (x * x + z * z).sqrt();
+        let center = if len == 0.0 {
+            Tuple::point(self.major, 0, 0)
+        } else {
+            Tuple::point(x * self.major / len, 0, z * self.major / len)
+        };
+        (object_point - center).normalize()
+    }
+    pub(super) fn material(&self) -> Material {
+        self.material
+    }
+    pub(super) fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+    pub(super) fn flags(&self) -> ShapeFlags {
+        self.flags
+    }
+    pub(super) fn set_flags(&mut self, flags: ShapeFlags) {
+        self.flags = flags;
+    }
+}
+
+impl PartialEq for Torus {
+    fn eq(&self, other: &Self) -> bool {
+        self.transform() == other.transform()
+            && self.material() == other.material()
+            && self.flags() == other.flags()
+            && self.major == other.major
+            && self.minor == other.minor
+    }
+}
@@ -0,0 +1,234 @@
+use super::Bounds;
+use crate::{transformation::Transform, Intersection, Material, Num, Pixel, Ray, Shape, ShapeFlags, Tuple, EPSILON};
+
+#[derive(Clone, Copy)]
+pub struct Triangle {
+    matrix: Transform,
+    material: Material,
+    flags: ShapeFlags,
+    p1: Tuple,
+    p2: Tuple,
+    p3: Tuple,
+    e1: Tuple,
+    e2: Tuple,
+    normal: Tuple,
+    colors: Option<[Pixel; 3]>,
+}
+
+impl Triangle {
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple) -> Triangle {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        Self {
+            matrix: Transform::identity(),
+            material: Material::default(),
+            flags: ShapeFlags::default(),
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal: e2.cross(&e1).normalize(),
+            colors: None,
+        }
+    }
+
+    pub fn p1(&self) -> Tuple {
+        self.p1
+    }
+    pub fn p2(&self) -> Tuple {
+        self.p2
+    }
+    pub fn p3(&self) -> Tuple {
+        self.p3
+    }
+    pub fn e1(&self) -> Tuple {
+        self.e1
+    }
+    pub fn e2(&self) -> Tuple {
+        self.e2
+    }
+    pub fn normal(&self) -> Tuple {
+        self.normal
+    }
+    pub fn vertex_colors(&self) -> Option<[Pixel; 3]> {
+        self.colors
+    }
+    pub fn set_vertex_colors(&mut self, colors: Option<[Pixel; 3]>) {
+        self.colors = colors;
+    }
+
+    // Vertex colors blended by the barycentric coordinates of a point on
+    // the triangle.
+    pub(super) fn color_at(&self, object_point: Tuple) -> Option<Pixel> {
+        let [c1, c2, c3] = self.colors?;
+        let p = object_point - self.p1;
+        let (d11, d12, d22) = (self.e1.dot(&self.e1), self.e1.dot(&self.e2), self.e2.dot(&self.e2));
+        let (dp1, dp2) = (p.dot(&self.e1), p.dot(&self.e2));
+        let denom = d11 * d22 - d12 * d12;
+        if denom.abs() < EPSILON {
+            return Some(c1);
+        }
+        let u = (d22 * dp1 - d12 * dp2) / denom;
+        let v = (d11 * dp2 - d12 * dp1) / denom;
+        Some(c2 * u + c3 * v + c1 * (1.0 - u - v))
+    }
+
+    pub(super) fn intersect(&self, ray: Ray, shape: &Shape) -> Vec<Intersection> {
+        match moller_trumbore(self.p1, self.e1, self.e2, ray) {
+            Some((t, u, v)) => vec![Intersection::with_uv(t, shape.clone(), u, v)],
+            None => vec![],
+        }
+    }
+
+    pub(super) fn set_transform(&mut self, m: Transform) {
+        self.matrix = m;
+    }
+    pub(super) fn transform(&self) -> &Transform {
+        &self.matrix
+    }
+    pub(super) fn bounds(&self) -> Bounds {
+        Bounds::of_points(&[self.p1, self.p2, self.p3])
+    }
+    pub(super) fn normal_at(&self, _object_point: Tuple) -> Tuple {
+        self.normal
+    }
+    pub(super) fn material(&self) -> Material {
+        self.material
+    }
+    pub(super) fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+    pub(super) fn flags(&self) -> ShapeFlags {
+        self.flags
+    }
+    pub(super) fn set_flags(&mut self, flags: ShapeFlags) {
+        self.flags = flags;
+    }
+}
+
+impl PartialEq for Triangle {
+    fn eq(&self, other: &Self) -> bool {
+        self.transform() == other.transform()
+            && self.material() == other.material()
+            && self.flags() == other.flags()
+            && self.p1 == other.p1
+            && self.p2 == other.p2
+            && self.p3 == other.p3
+            && self.colors == other.colors
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct SmoothTriangle {
+    triangle: Triangle,
+    n1: Tuple,
+    n2: Tuple,
+    n3: Tuple,
+}
+
+impl SmoothTriangle {
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple, n1: Tuple, n2: Tuple, n3: Tuple) -> SmoothTriangle {
+        Self {
+            triangle: Triangle::new(p1, p2, p3),
+            n1,
+            n2,
+            n3,
+        }
+    }
+
+    pub fn p1(&self) -> Tuple {
+        self.triangle.p1
+    }
+    pub fn p2(&self) -> Tuple {
+        self.triangle.p2
+    }
+    pub fn p3(&self) -> Tuple {
+        self.triangle.p3
+    }
+    pub fn n1(&self) -> Tuple {
+        self.n1
+    }
+    pub fn n2(&self) -> Tuple {
+        self.n2
+    }
+    pub fn n3(&self) -> Tuple {
+        self.n3
+    }
+    pub fn vertex_colors(&self) -> Option<[Pixel; 3]> {
+        self.triangle.vertex_colors()
+    }
+    pub fn set_vertex_colors(&mut self, colors: Option<[Pixel; 3]>) {
+        self.triangle.set_vertex_colors(colors);
+    }
+
+    pub(super) fn color_at(&self, object_point: Tuple) -> Option<Pixel> {
+        self.triangle.color_at(object_point)
+    }
+
+    pub(super) fn intersect(&self, ray: Ray, shape: &Shape) -> Vec<Intersection> {
+        self.triangle.intersect(ray, shape)
+    }
+
+    pub(super) fn set_transform(&mut self, m: Transform) {
+        self.triangle.set_transform(m);
+    }
+    pub(super) fn transform(&self) -> &Transform {
+        self.triangle.transform()
+    }
+    pub(super) fn bounds(&self) -> Bounds {
+        self.triangle.bounds()
+    }
+    // Without a hit there are no barycentric coordinates, so fall back to the face normal.
+    pub(super) fn normal_at(&self, object_point: Tuple) -> Tuple {
+        self.triangle.normal_at(object_point)
+    }
+    pub(super) fn normal_at_uv(&self, u: Num, v: Num) -> Tuple {
+        self.n2 * u + self.n3 * v + self.n1 * (1.0 - u - v)
+    }
+    pub(super) fn material(&self) -> Material {
+        self.triangle.material()
+    }
+    pub(super) fn set_material(&mut self, material: Material) {
+        self.triangle.set_material(material);
+    }
+    pub(super) fn flags(&self) -> ShapeFlags {
+        self.triangle.flags()
+    }
+    pub(super) fn set_flags(&mut self, flags: ShapeFlags) {
+        self.triangle.set_flags(flags);
+    }
+}
+
+impl PartialEq for SmoothTriangle {
+    fn eq(&self, other: &Self) -> bool {
+        self.triangle == other.triangle
+            && self.n1 == other.n1
+            && self.n2 == other.n2
+            && self.n3 == other.n3
+    }
+}
+
+pub(super) fn moller_trumbore(p1: Tuple, e1: Tuple, e2: Tuple, ray: Ray) -> Option<(Num, Num, Num)> {
+    let dir_cross_e2 = ray.direction().cross(&e2);
+    let det = e1.dot(&dir_cross_e2);
+    if det.abs() < EPSILON {
+        return None;
+    }
+
+    let f = 1.0 / det;
+    let p1_to_origin = ray.origin() - p1;
+    let u = f * p1_to_origin.dot(&dir_cross_e2);
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let origin_cross_e1 = p1_to_origin.cross(&e1);
+    let v = f * ray.direction().dot(&origin_cross_e1);
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = f * e2.dot(&origin_cross_e1);
+    Some((t, u, v))
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::approx_constant, clippy::clone_on_copy)]
+
 mod chapter1 {
     use crate::{equal, tuple::Tuple};
 
@@ -580,7 +582,7 @@ mod chapter5 {
             let r = Ray::new(Tuple::point(0, 2, -5), Tuple::vector(0, 0, 1));
             let s = Shape::Sphere(Sphere::new());
             let xs = s.intersect(r);
-            assert!(xs.len() == 0);
+            assert!(xs.is_empty());
         }
 
         {
@@ -606,14 +608,14 @@ mod chapter5 {
     fn tracking_intersections() {
         {
             let s = Shape::Sphere(Sphere::new());
-            let i = Intersection::new(3.5, s);
+            let i = Intersection::new(3.5, s.clone());
             assert!(equal(i.t(), 3.5));
             assert!(i.object() == s);
         }
 
         {
             let s = Shape::Sphere(Sphere::new());
-            let i1 = Intersection::new(1, s);
+            let i1 = Intersection::new(1, s.clone());
             let i2 = Intersection::new(2, s);
             let xs = Intersection::make(i1, i2);
             assert!(xs.len() == 2);
@@ -635,29 +637,29 @@ mod chapter5 {
     fn identifying_hits() {
         {
             let s = Shape::sphere();
-            let i1 = Intersection::new(1, s);
+            let i1 = Intersection::new(1, s.clone());
             let i2 = Intersection::new(2, s);
-            let xs = Intersection::make(i2, i1);
+            let xs = Intersection::make(i2, i1.clone());
             let i = intersection::hit(xs).unwrap();
             assert!(i == i1);
         }
 
         {
             let s = Shape::sphere();
-            let i1 = Intersection::new(-1, s);
+            let i1 = Intersection::new(-1, s.clone());
             let i2 = Intersection::new(1, s);
-            let xs = Intersection::make(i2, i1);
+            let xs = Intersection::make(i2.clone(), i1);
             let i = intersection::hit(xs).unwrap();
             assert!(i == i2);
         }
 
         {
             let s = Shape::sphere();
-            let i1 = Intersection::new(5, s);
-            let i2 = Intersection::new(7, s);
-            let i3 = Intersection::new(-3, s);
+            let i1 = Intersection::new(5, s.clone());
+            let i2 = Intersection::new(7, s.clone());
+            let i3 = Intersection::new(-3, s.clone());
             let i4 = Intersection::new(2, s);
-            let xs = vec![i1, i2, i3, i4];
+            let xs = vec![i1, i2, i3, i4.clone()];
             let i = intersection::hit(xs).unwrap();
             assert!(i == i4);
         }
@@ -689,14 +691,14 @@ mod chapter5 {
         {
             let mut s = Shape::sphere();
             let t = Matrix4x4::translation(2, 3, 4);
-            s.set_transform(t);
+            s.set_transform(t).unwrap();
             assert!(s.transform() == t);
         }
 
         {
             let r = Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1));
             let mut s = Shape::sphere();
-            s.set_transform(Matrix4x4::scaling(2, 2, 2));
+            s.set_transform(Matrix4x4::scaling(2, 2, 2)).unwrap();
             let xs = s.intersect(r);
             assert!(xs.len() == 2);
             assert!(equal(xs[0].t(), 3.0));
@@ -706,9 +708,9 @@ mod chapter5 {
         {
             let r = Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1));
             let mut s = Shape::sphere();
-            s.set_transform(Matrix4x4::translation(5, 0, 0));
+            s.set_transform(Matrix4x4::translation(5, 0, 0)).unwrap();
             let xs = s.intersect(r);
-            assert!(xs.len() == 0);
+            assert!(xs.is_empty());
         }
     }
 
@@ -761,19 +763,19 @@ mod chapter6 {
     fn surface_normals() {
         {
             let s = Shape::sphere();
-            let n = s.normal_at(Tuple::point(1, 0, 0));
+            let n = s.normal_at(Tuple::point(1, 0, 0)).unwrap();
             assert!(n == Tuple::vector(1, 0, 0));
         }
 
         {
             let s = Shape::sphere();
-            let n = s.normal_at(Tuple::point(0, 1, 0));
+            let n = s.normal_at(Tuple::point(0, 1, 0)).unwrap();
             assert!(n == Tuple::vector(0, 1, 0));
         }
 
         {
             let s = Shape::sphere();
-            let n = s.normal_at(Tuple::point(0, 0, 1));
+            let n = s.normal_at(Tuple::point(0, 0, 1)).unwrap();
             assert!(n == Tuple::vector(0, 0, 1));
         }
 
@@ -783,7 +785,7 @@ mod chapter6 {
                 3.0_f64.sqrt() / 3.0,
                 3.0_f64.sqrt() / 3.0,
                 3.0_f64.sqrt() / 3.0,
-            ));
+            )).unwrap();
             assert!(
                 n == Tuple::vector(
                     3.0_f64.sqrt() / 3.0,
@@ -799,14 +801,14 @@ mod chapter6 {
                 3.0_f64.sqrt() / 3.0,
                 3.0_f64.sqrt() / 3.0,
                 3.0_f64.sqrt() / 3.0,
-            ));
+            )).unwrap();
             assert!(n == n.normalize());
         }
 
         {
             let mut s = Shape::sphere();
-            s.set_transform(Matrix4x4::translation(0, 1, 0));
-            let n = s.normal_at(Tuple::point(0, 1.70711, -0.70711));
+            s.set_transform(Matrix4x4::translation(0, 1, 0)).unwrap();
+            let n = s.normal_at(Tuple::point(0, 1.70711, -0.70711)).unwrap();
             assert!(n == Tuple::vector(0.0, 0.70711, -0.70711));
         }
 
@@ -815,8 +817,8 @@ mod chapter6 {
             let m = TransformationBuilder::rotation_z(PI / 5.0)
                 .scale(1, 0.5, 1)
                 .build();
-            s.set_transform(m);
-            let n = s.normal_at(Tuple::point(0, 2.0_f64.sqrt() / 2.0, -2.0_f64.sqrt() / 2.0));
+            s.set_transform(m).unwrap();
+            let n = s.normal_at(Tuple::point(0, 2.0_f64.sqrt() / 2.0, -2.0_f64.sqrt() / 2.0)).unwrap();
             assert!(n == Tuple::vector(0.0, 0.97014, -0.24254));
         }
     }
@@ -943,7 +945,7 @@ mod chapter6 {
                 let xs = shape.intersect(r);
                 if let Some(hit) = intersection::hit(xs) {
                     let point = r.position(hit.t());
-                    let normal = hit.object().normal_at(point);
+                    let normal = hit.object().normal_at(point).unwrap();
                     let eye = -r.direction();
 
                     let color = hit
@@ -969,7 +971,7 @@ mod chapter7 {
     fn building_a_world() {
         {
             let w = World::new();
-            assert!(w.objects().len() == 0);
+            assert!(w.objects().is_empty());
             assert!(w.light().is_none());
         }
 
@@ -982,7 +984,7 @@ mod chapter7 {
             s1_m.set_specular(0.2);
             s1.set_material(s1_m);
             let mut s2 = Shape::sphere();
-            s2.set_transform(Matrix4x4::scaling(0.5, 0.5, 0.5));
+            s2.set_transform(Matrix4x4::scaling(0.5, 0.5, 0.5)).unwrap();
 
             let w = World::default();
             assert!(w.light().unwrap() == light);
@@ -1005,7 +1007,7 @@ mod chapter7 {
             let r = Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1));
             let shape = Shape::sphere();
             let i = Intersection::new(4, shape);
-            let comps = i.prepare_computations(r, vec![i]);
+            let comps = i.prepare_computations(r, vec![i.clone()]);
             assert!(equal(comps.t(), i.t()));
             assert!(comps.object() == i.object());
             assert!(comps.point() == Tuple::point(0, 0, -1));
@@ -1017,27 +1019,27 @@ mod chapter7 {
             let r = Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1));
             let shape = Shape::sphere();
             let i = Intersection::new(4, shape);
-            let comps = i.prepare_computations(r, vec![i]);
-            assert!(comps.inside() == false);
+            let comps = i.prepare_computations(r, vec![i.clone()]);
+            assert!(!comps.inside());
         }
 
         {
             let r = Ray::new(Tuple::point(0, 0, 0), Tuple::vector(0, 0, 1));
             let shape = Shape::sphere();
             let i = Intersection::new(1, shape);
-            let comps = i.prepare_computations(r, vec![i]);
+            let comps = i.prepare_computations(r, vec![i.clone()]);
 
             assert!(comps.point() == Tuple::point(0, 0, 1));
             assert!(comps.eyev() == Tuple::vector(0, 0, -1));
-            assert!(comps.inside() == true);
+            assert!(comps.inside());
         }
 
         {
             let w = World::default();
             let r = Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1));
-            let shape = w.objects()[0];
+            let shape = w.objects()[0].clone();
             let i = Intersection::new(4, shape);
-            let comps = i.prepare_computations(r, vec![i]);
+            let comps = i.prepare_computations(r, vec![i.clone()]);
             let c = w.shade_hit(comps, 0);
             assert!(c == Pixel::rgb(0.38066, 0.47583, 0.2855));
         }
@@ -1046,9 +1048,9 @@ mod chapter7 {
             let mut w = World::default();
             w.set_light(Light::point(Tuple::point(0, 0.25, 0), Pixel::white()));
             let r = Ray::new(Tuple::point(0, 0, 0), Tuple::vector(0, 0, 1));
-            let shape = w.objects()[1];
+            let shape = w.objects()[1].clone();
             let i = Intersection::new(0.5, shape);
-            let comps = i.prepare_computations(r, vec![i]);
+            let comps = i.prepare_computations(r, vec![i.clone()]);
             let c = w.shade_hit(comps, 0);
             assert!(c == Pixel::rgb(0.90498, 0.90498, 0.90498));
         }
@@ -1169,7 +1171,7 @@ mod chapter7 {
                 TransformationBuilder::translation(0, -2, 5)
                     .rotate_y(PI / 4.0)
                     .build(),
-            );
+            ).unwrap();
             let r = c.ray_for_pixel(100, 50);
             assert!(r.origin() == Tuple::point(0, 2, -5));
             assert!(r.direction() == Tuple::vector(2f64.sqrt() / 2.0, 0.0, -2f64.sqrt() / 2.0));
@@ -1186,7 +1188,7 @@ mod chapter7 {
                     Tuple::point(0, 0, 0),
                     Tuple::vector(0, 1, 0),
                 ),
-            );
+            ).unwrap();
             let image: Canvas = c.render(w);
             assert!(image.get(5, 5) == Pixel::rgb(0.38066, 0.47583, 0.2855));
         }
@@ -1195,7 +1197,7 @@ mod chapter7 {
     #[test]
     fn putting_it_together() {
         let mut floor = Shape::sphere();
-        floor.set_transform(Matrix4x4::scaling(10, 0.01, 10));
+        floor.set_transform(Matrix4x4::scaling(10, 0.01, 10)).unwrap();
         let mut floor_mat = Material::default();
         floor_mat.set_color(Pixel::rgb(1.0, 0.9, 0.9));
         floor_mat.set_specular(0);
@@ -1207,7 +1209,7 @@ mod chapter7 {
                 * Matrix4x4::rotation_y(-PI / 4.0)
                 * Matrix4x4::rotation_x(PI / 2.0)
                 * Matrix4x4::scaling(10, 0.01, 10),
-        );
+        ).unwrap();
         left_wall.set_material(floor_mat);
 
         let mut right_wall = Shape::sphere();
@@ -1216,11 +1218,11 @@ mod chapter7 {
                 * Matrix4x4::rotation_y(PI / 4.0)
                 * Matrix4x4::rotation_x(PI / 2.0)
                 * Matrix4x4::scaling(10, 0.01, 10),
-        );
+        ).unwrap();
         right_wall.set_material(floor_mat);
 
         let mut middle = Shape::sphere();
-        middle.set_transform(Matrix4x4::translation(-0.5, 1, 0.5));
+        middle.set_transform(Matrix4x4::translation(-0.5, 1, 0.5)).unwrap();
         let mut middle_mat = Material::default();
         middle_mat.set_color(Pixel::rgb(0.1, 1.0, 0.5));
         middle_mat.set_diffuse(0.7);
@@ -1230,7 +1232,7 @@ mod chapter7 {
         let mut right = Shape::sphere();
         right.set_transform(
             Matrix4x4::translation(1.5, 0.5, -0.5) * Matrix4x4::scaling(0.5, 0.5, 0.5),
-        );
+        ).unwrap();
         let mut right_mat = Material::default();
         right_mat.set_color(Pixel::rgb(0.5, 1.0, 0.1));
         right_mat.set_diffuse(0.7);
@@ -1240,7 +1242,7 @@ mod chapter7 {
         let mut left = Shape::sphere();
         left.set_transform(
             Matrix4x4::translation(-1.5, 0.33, -0.75) * Matrix4x4::scaling(0.33, 0.33, 0.33),
-        );
+        ).unwrap();
         let mut left_mat = Material::default();
         left_mat.set_color(Pixel::rgb(1.0, 0.8, 0.1));
         left_mat.set_diffuse(0.7);
@@ -1260,7 +1262,7 @@ mod chapter7 {
                 Tuple::point(0, 1, 0),
                 Tuple::vector(0, 1, 0),
             ),
-        );
+        ).unwrap();
         let _canvas = camera.render(world);
     }
 }
@@ -1291,25 +1293,25 @@ mod chapter8 {
         {
             let w = World::default();
             let p = Tuple::point(0, 10, 0);
-            assert!(!w.is_shadowed(p))
+            assert!(w.is_shadowed(p) == Pixel::white());
         }
 
         {
             let w = World::default();
             let p = Tuple::point(10, -10, 10);
-            assert!(w.is_shadowed(p));
+            assert!(w.is_shadowed(p) == Pixel::black());
         }
 
         {
             let w = World::default();
             let p = Tuple::point(-20, 20, -20);
-            assert!(!w.is_shadowed(p));
+            assert!(w.is_shadowed(p) == Pixel::white());
         }
 
         {
             let w = World::default();
             let p = Tuple::point(-2, 2, -2);
-            assert!(!w.is_shadowed(p));
+            assert!(w.is_shadowed(p) == Pixel::white());
         }
     }
 
@@ -1320,11 +1322,11 @@ mod chapter8 {
             w.set_light(Light::point(Tuple::point(0, 0, -10), Pixel::white()));
             let s1 = Shape::sphere();
             let mut s2 = Shape::sphere();
-            s2.set_transform(Matrix4x4::translation(0, 0, 10));
-            w.set_objects(vec![s1, s2]);
+            s2.set_transform(Matrix4x4::translation(0, 0, 10)).unwrap();
+            w.set_objects(vec![s1, s2.clone()]);
             let r = Ray::new(Tuple::point(0, 0, 5), Tuple::vector(0, 0, 1));
             let i = Intersection::new(4, s2);
-            let comps = i.prepare_computations(r, vec![i]);
+            let comps = i.prepare_computations(r, vec![i.clone()]);
             let c = w.shade_hit(comps, 0);
             assert!(c == Pixel::rgb(0.1, 0.1, 0.1));
         }
@@ -1332,10 +1334,10 @@ mod chapter8 {
         {
             let r = Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1));
             let mut shape = Shape::sphere();
-            shape.set_transform(Matrix4x4::translation(0, 0, 1));
+            shape.set_transform(Matrix4x4::translation(0, 0, 1)).unwrap();
 
             let i = Intersection::new(5, shape);
-            let comps = i.prepare_computations(r, vec![i]);
+            let comps = i.prepare_computations(r, vec![i.clone()]);
             assert!(comps.over_point().get_z() < -EPSILON / 2.0);
             assert!(comps.point().get_z() > comps.over_point().get_z());
         }
@@ -1354,7 +1356,7 @@ mod chapter9 {
 
         {
             let mut s = Shape::test_shape();
-            s.set_transform(Matrix4x4::translation(2, 3, 4));
+            s.set_transform(Matrix4x4::translation(2, 3, 4)).unwrap();
             assert!(s.transform() == Matrix4x4::translation(2, 3, 4));
         }
 
@@ -1375,7 +1377,7 @@ mod chapter9 {
         {
             let r = Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1));
             let mut s = Shape::test_shape();
-            s.set_transform(Matrix4x4::scaling(2, 2, 2));
+            s.set_transform(Matrix4x4::scaling(2, 2, 2)).unwrap();
             let _xs = s.intersect(r);
             unsafe {
                 assert!(shape::SAVED_RAY.unwrap().origin() == Tuple::point(0, 0, -2.5));
@@ -1386,7 +1388,7 @@ mod chapter9 {
         {
             let r = Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1));
             let mut s = Shape::test_shape();
-            s.set_transform(Matrix4x4::translation(5, 0, 0));
+            s.set_transform(Matrix4x4::translation(5, 0, 0)).unwrap();
             let _xs = s.intersect(r);
             unsafe {
                 assert!(shape::SAVED_RAY.unwrap().origin() == Tuple::point(-5, 0, -5));
@@ -1396,16 +1398,16 @@ mod chapter9 {
 
         {
             let mut s = Shape::test_shape();
-            s.set_transform(Matrix4x4::translation(0, 1, 0));
-            let n = s.normal_at(Tuple::point(0, 1.70711, -0.70711));
+            s.set_transform(Matrix4x4::translation(0, 1, 0)).unwrap();
+            let n = s.normal_at(Tuple::point(0, 1.70711, -0.70711)).unwrap();
             assert!(n == Tuple::vector(0, 0.70711, -0.70711));
         }
 
         {
             let mut s = Shape::test_shape();
             let m = Matrix4x4::scaling(1, 0.5, 1) * Matrix4x4::rotation_z(PI / 5.0);
-            s.set_transform(m);
-            let n = s.normal_at(Tuple::point(0, 2f64.sqrt() / 2.0, -2f64.sqrt() / 2.0));
+            s.set_transform(m).unwrap();
+            let n = s.normal_at(Tuple::point(0, 2f64.sqrt() / 2.0, -2f64.sqrt() / 2.0)).unwrap();
             assert!(n == Tuple::vector(0, 0.97014, -0.24254));
         }
     }
@@ -1414,9 +1416,9 @@ mod chapter9 {
     fn implementing_a_plane() {
         {
             let p = Shape::plane();
-            let n1 = p.normal_at(Tuple::point(0, 0, 0));
-            let n2 = p.normal_at(Tuple::point(10, 0, -10));
-            let n3 = p.normal_at(Tuple::point(-5, 0, 150));
+            let n1 = p.normal_at(Tuple::point(0, 0, 0)).unwrap();
+            let n2 = p.normal_at(Tuple::point(10, 0, -10)).unwrap();
+            let n3 = p.normal_at(Tuple::point(-5, 0, 150)).unwrap();
             assert!(n1 == Tuple::vector(0, 1, 0));
             assert!(n2 == Tuple::vector(0, 1, 0));
             assert!(n3 == Tuple::vector(0, 1, 0));
@@ -1426,14 +1428,14 @@ mod chapter9 {
             let p = Shape::plane();
             let r = Ray::new(Tuple::point(0, 10, 0), Tuple::vector(0, 0, 1));
             let xs = p.intersect(r);
-            assert!(xs.len() == 0);
+            assert!(xs.is_empty());
         }
 
         {
             let p = Shape::plane();
             let r = Ray::new(Tuple::point(0, 0, 0), Tuple::vector(0, 0, 1));
             let xs = p.intersect(r);
-            assert!(xs.len() == 0);
+            assert!(xs.is_empty());
         }
 
         {
@@ -1512,7 +1514,7 @@ mod chapter10{
 
         {
             let mut object = Shape::sphere();
-            object.set_transform(Matrix4x4::scaling(2, 2, 2));
+            object.set_transform(Matrix4x4::scaling(2, 2, 2)).unwrap();
             let pattern = Pattern::stripe(white, black);
             let c = pattern.at_object(&object, Tuple::point(1.5, 0, 0));
             assert!(c == white);
@@ -1521,16 +1523,16 @@ mod chapter10{
         {
             let object = Shape::sphere();
             let mut pattern = Pattern::stripe(white, black);
-            pattern.set_transform(Matrix4x4::scaling(2, 2, 2));
+            pattern.set_transform(Matrix4x4::scaling(2, 2, 2)).unwrap();
             let c = pattern.at_object(&object, Tuple::point(1.5, 0, 0));
             assert!(c == white);
         }
 
         {
             let mut object = Shape::sphere();
-            object.set_transform(Matrix4x4::scaling(2, 2, 2));
+            object.set_transform(Matrix4x4::scaling(2, 2, 2)).unwrap();
             let mut pattern = Pattern::stripe(white, black);
-            pattern.set_transform(Matrix4x4::translation(0.5, 0, 0));
+            pattern.set_transform(Matrix4x4::translation(0.5, 0, 0)).unwrap();
             let c = pattern.at_object(&object, Tuple::point(2.5, 0, 0));
             assert!(c == white);
         }
@@ -1591,7 +1593,7 @@ mod chapter10{
 mod chapter11{
     use std::vec;
 
-    use crate::{Material, equal, Shape, Ray, Tuple, Intersection, World, Pixel, Matrix4x4, Light, EPSILON, shape, Pattern, Camera};
+    use crate::{Material, equal, Shape, Ray, Tuple, Intersection, World, Pixel, Matrix4x4, Light, EPSILON, Pattern, Camera};
 
 
     #[test]
@@ -1605,19 +1607,19 @@ mod chapter11{
             let shape = Shape::plane();
             let r = Ray::new(Tuple::point(0, 1, -1), Tuple::vector(0, -2f64.sqrt()/2.0, 2f64.sqrt()/2.0));
             let i = Intersection::new(2f64.sqrt(), shape);
-            let comps = i.prepare_computations(r, vec![i]);
+            let comps = i.prepare_computations(r, vec![i.clone()]);
             assert!(comps.reflectv() == Tuple::vector(0, 2f64.sqrt()/2.0, 2f64.sqrt()/2.0));
         }
 
         {
             let w = World::default();
             let r = Ray::new(Tuple::point(0, 0, 0), Tuple::vector(0, 0, 1));
-            let mut shape = w.objects()[1];
+            let mut shape = w.objects()[1].clone();
             let mut shape_mat = shape.material();
             shape_mat.set_ambient(1);
             shape.set_material(shape_mat);
             let i = Intersection::new(1, shape);
-            let comps = i.prepare_computations(r, vec![i]);
+            let comps = i.prepare_computations(r, vec![i.clone()]);
             let color = w.reflected_color(comps, 5);
             assert!(color == Pixel::black());
         }
@@ -1629,13 +1631,13 @@ mod chapter11{
             let mut shape_mat = shape.material();
             shape_mat.set_reflective(0.5);
             shape.set_material(shape_mat);
-            shape.set_transform(Matrix4x4::translation(0, -1, 0));
-            objs.push(shape);
+            shape.set_transform(Matrix4x4::translation(0, -1, 0)).unwrap();
+            objs.push(shape.clone());
             w.set_objects(objs);
 
             let r = Ray::new(Tuple::point(0, 0, -3), Tuple::vector(0, -2f64.sqrt()/2.0, 2f64.sqrt()/2.0));
             let i = Intersection::new(2f64.sqrt(), shape);
-            let comps = i.prepare_computations(r, vec![i]);
+            let comps = i.prepare_computations(r, vec![i.clone()]);
             let color = w.reflected_color(comps, 5);
             assert!(color == Pixel::rgb(0.19032, 0.2379, 0.14274));
         }
@@ -1647,13 +1649,13 @@ mod chapter11{
             let mut shape_mat = shape.material();
             shape_mat.set_reflective(0.5);
             shape.set_material(shape_mat);
-            shape.set_transform(Matrix4x4::translation(0, -1, 0));
-            objs.push(shape);
+            shape.set_transform(Matrix4x4::translation(0, -1, 0)).unwrap();
+            objs.push(shape.clone());
             w.set_objects(objs);
 
             let r = Ray::new(Tuple::point(0, 0, -3), Tuple::vector(0, -2f64.sqrt()/2.0, 2f64.sqrt()/2.0));
             let i = Intersection::new(2f64.sqrt(), shape);
-            let comps = i.prepare_computations(r, vec![i]);
+            let comps = i.prepare_computations(r, vec![i.clone()]);
             let color = w.shade_hit(comps, 5);
             assert!(color == Pixel::rgb(0.87677, 0.92436, 0.82918));
         }
@@ -1665,10 +1667,10 @@ mod chapter11{
             let mut shape_mat = lower.material();
             shape_mat.set_reflective(1);
             lower.set_material(shape_mat);
-            lower.set_transform(Matrix4x4::translation(0, -1, 0));
+            lower.set_transform(Matrix4x4::translation(0, -1, 0)).unwrap();
             let mut upper = Shape::plane();
             upper.set_material(shape_mat);
-            upper.set_transform(Matrix4x4::translation(0, 1, 0));
+            upper.set_transform(Matrix4x4::translation(0, 1, 0)).unwrap();
             w.set_objects(vec![upper, lower]);
 
             let r = Ray::new(Tuple::point(0, 0, 0), Tuple::vector(0, 1, 0));
@@ -1682,13 +1684,13 @@ mod chapter11{
             let mut shape_mat = shape.material();
             shape_mat.set_reflective(0.5);
             shape.set_material(shape_mat);
-            shape.set_transform(Matrix4x4::translation(0, -1, 0));
-            objs.push(shape);
+            shape.set_transform(Matrix4x4::translation(0, -1, 0)).unwrap();
+            objs.push(shape.clone());
             w.set_objects(objs);
 
             let r = Ray::new(Tuple::point(0, 0, -3), Tuple::vector(0, -2f64.sqrt()/2.0, 2f64.sqrt()/2.0));
             let i = Intersection::new(2f64.sqrt(), shape);
-            let comps = i.prepare_computations(r, vec![i]);
+            let comps = i.prepare_computations(r, vec![i.clone()]);
             let color = w.reflected_color(comps, 0);
             assert!(color == Pixel::black());
         }
@@ -1711,55 +1713,52 @@ mod chapter11{
 
         {
             let mut a = glass_sphere();
-            a.set_transform(Matrix4x4::scaling(2, 2, 2));
+            a.set_transform(Matrix4x4::scaling(2, 2, 2)).unwrap();
             let mut a_mat = a.material();
             a_mat.set_refractive_index(1.5);
             a.set_material(a_mat);
 
             let mut b = glass_sphere();
-            b.set_transform(Matrix4x4::translation(0, 0, -0.25));
+            b.set_transform(Matrix4x4::translation(0, 0, -0.25)).unwrap();
             let mut b_mat = b.material();
             b_mat.set_refractive_index(2);
             b.set_material(b_mat);
 
             let mut c = glass_sphere();
-            c.set_transform(Matrix4x4::translation(0, 0, 0.25));
+            c.set_transform(Matrix4x4::translation(0, 0, 0.25)).unwrap();
             let mut c_mat = c.material();
             c_mat.set_refractive_index(2.5);
             c.set_material(c_mat);
 
             let r = Ray::new(Tuple::point(0, 0, -4), Tuple::vector(0, 0, 1));
-            let xs = vec![Intersection::new(2, a), Intersection::new(2.75, b), Intersection::new(3.25, c), Intersection::new(4.75, b), Intersection::new(5.25, c), Intersection::new(6, a)];
+            let xs = vec![Intersection::new(2, a.clone()), Intersection::new(2.75, b.clone()), Intersection::new(3.25, c.clone()), Intersection::new(4.75, b), Intersection::new(5.25, c), Intersection::new(6, a)];
 
-            let result = vec![(1.0, 1.5),(1.5, 2.0), (2.0, 2.5), (2.5, 2.5), (2.5, 1.5), (1.5, 1.0)];
+            let result = [(1.0, 1.5),(1.5, 2.0), (2.0, 2.5), (2.5, 2.5), (2.5, 1.5), (1.5, 1.0)];
 
-            let mut i = 0;
-            for x in xs.clone() {
+            for (i, x) in xs.clone().into_iter().enumerate() {
                 let comps = x.prepare_computations(r, xs.clone());
                 assert!(equal(comps.n1(), result[i].0));
                 assert!(equal(comps.n2(), result[i].1));
-
-                i += 1;
             }
         }
 
         {
             let r = Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1));
             let mut shape = glass_sphere();
-            shape.set_transform(Matrix4x4::translation(0, 0, 1));
+            shape.set_transform(Matrix4x4::translation(0, 0, 1)).unwrap();
             let i = Intersection::new(5, shape);
-            let xs = vec![i];
+            let xs = vec![i.clone()];
             let comps = i.prepare_computations(r, xs);
             assert!(comps.under_point().get_z() > EPSILON / 2.0);
             assert!(comps.point().get_z() < comps.under_point().get_z());
         }
 
         {
-            let mut w = World::default();
-            let shape = w.objects()[0];
+            let w = World::default();
+            let shape = w.objects()[0].clone();
 
             let r = Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1));
-            let xs = vec![Intersection::new(4, shape), Intersection::new(6, shape)];
+            let xs = vec![Intersection::new(4, shape.clone()), Intersection::new(6, shape)];
             let comps = xs[0].clone().prepare_computations(r, xs);
             let c = w.refracted_color(comps, 5);
             assert!(c == Pixel::black());
@@ -1775,7 +1774,7 @@ mod chapter11{
             w.set_objects(objs.clone());
 
             let r = Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1));
-            let xs = vec![Intersection::new(4, objs[0]), Intersection::new(6, objs[0])];
+            let xs = vec![Intersection::new(4, objs[0].clone()), Intersection::new(6, objs[0].clone())];
             let comps = xs[0].clone().prepare_computations(r, xs);
             let c = w.refracted_color(comps, 0);
             assert!(c == Pixel::black());
@@ -1791,7 +1790,7 @@ mod chapter11{
             w.set_objects(objs.clone());
 
             let r = Ray::new(Tuple::point(0, 0, 2f64.sqrt()/2.0), Tuple::vector(0, 1, 0));
-            let xs = vec![Intersection::new(-2f64.sqrt()/2.0, objs[0]), Intersection::new(2f64.sqrt()/2.0, objs[0])];
+            let xs = vec![Intersection::new(-2f64.sqrt()/2.0, objs[0].clone()), Intersection::new(2f64.sqrt()/2.0, objs[0].clone())];
 
             let comps = xs[1].clone().prepare_computations(r, xs);
             let c = w.refracted_color(comps, 5);
@@ -1825,19 +1824,19 @@ mod chapter11{
             let mut objs = w.objects();
 
             let mut floor = Shape::plane();
-            floor.set_transform(Matrix4x4::translation(0, -1, 0));
+            floor.set_transform(Matrix4x4::translation(0, -1, 0)).unwrap();
             let mut floor_mat = floor.material();
             floor_mat.set_transparency(0.5);
             floor_mat.set_refractive_index(1.5);
             floor.set_material(floor_mat);
-            objs.push(floor);
+            objs.push(floor.clone());
             
             let mut ball = Shape::sphere();
             let mut ball_mat = ball.material();
             ball_mat.set_color(Pixel::red());
             ball_mat.set_ambient(0.5);
             ball.set_material(ball_mat);
-            ball.set_transform(Matrix4x4::translation(0, -3.5, -0.5));
+            ball.set_transform(Matrix4x4::translation(0, -3.5, -0.5)).unwrap();
             objs.push(ball);
 
             w.set_objects(objs);
@@ -1855,7 +1854,7 @@ mod chapter11{
         {
             let shape = glass_sphere();
             let r = Ray::new(Tuple::point(0, 0, 2f64.sqrt()/2.0), Tuple::vector(0, 1, 0));
-            let xs = vec![Intersection::new(-2f64.sqrt()/2.0, shape), Intersection::new(2f64.sqrt()/2.0, shape)];
+            let xs = vec![Intersection::new(-2f64.sqrt()/2.0, shape.clone()), Intersection::new(2f64.sqrt()/2.0, shape)];
             let comps = xs[1].clone().prepare_computations(r, xs);
             let reflectance = comps.schlick();
             assert!(equal(reflectance, 1.0));
@@ -1864,7 +1863,7 @@ mod chapter11{
         {
             let shape = glass_sphere();
             let r = Ray::new(Tuple::point(0, 0, 0), Tuple::vector(0, 1, 0));
-            let xs = vec![Intersection::new(-1, shape), Intersection::new(1, shape)];
+            let xs = vec![Intersection::new(-1, shape.clone()), Intersection::new(1, shape)];
             let comps = xs[1].clone().prepare_computations(r, xs);
             let reflectance = comps.schlick();
             assert!(equal(reflectance, 0.04));
@@ -1884,7 +1883,7 @@ mod chapter11{
             let r = Ray::new(Tuple::point(0, 0, -3), Tuple::vector(0, -2f64.sqrt()/2.0, 2f64.sqrt()/2.0));
 
             let mut floor = Shape::plane();
-            floor.set_transform(Matrix4x4::translation(0, -1, 0));
+            floor.set_transform(Matrix4x4::translation(0, -1, 0)).unwrap();
             let mut floor_mat = floor.material();
             floor_mat.set_reflective(0.5);
             floor_mat.set_transparency(0.5);
@@ -1892,14 +1891,14 @@ mod chapter11{
             floor.set_material(floor_mat);
 
             let mut ball = Shape::sphere();
-            ball.set_transform(Matrix4x4::translation(0, -3.5, -0.5));
+            ball.set_transform(Matrix4x4::translation(0, -3.5, -0.5)).unwrap();
             let mut ball_mat = ball.material();
             ball_mat.set_color(Pixel::red());
             ball_mat.set_ambient(0.5);
             ball.set_material(ball_mat);
 
             let mut objs = w.objects();
-            objs.append(&mut vec![floor, ball]);
+            objs.append(&mut vec![floor.clone(), ball]);
             w.set_objects(objs);
 
             let xs = vec![Intersection::new(2f64.sqrt(), floor)];
@@ -1915,7 +1914,7 @@ mod chapter11{
     w.set_light(Light::point(Tuple::point(2, 10, -5), Pixel::rgb(0.9, 0.9, 0.9)));
 
     let mut wall = Shape::plane();
-    wall.set_transform(Matrix4x4::translation(0, 0, 10) * Matrix4x4::rotation_x(1.5708));
+    wall.set_transform(Matrix4x4::translation(0, 0, 10) * Matrix4x4::rotation_x(1.5708)).unwrap();
     let mut floor_mat = wall.material();
     floor_mat.set_pattern(Pattern::checkers(Pixel::rgb(0.15,0.15,0.15), Pixel::rgb(0.85,0.85,0.85)));
     floor_mat.set_ambient(0.8);
@@ -1934,10 +1933,10 @@ mod chapter11{
     glass_sphere_mat.set_color(Pixel::white());
     glass_sphere_mat.set_specular(0.9);
     glass_sphere.set_material(glass_sphere_mat);
-    // glass_sphere.set_transform(Matrix4x4::scaling(0.5, 0.5, 0.5));
+    // glass_sphere.set_transform(Matrix4x4::scaling(0.5, 0.5, 0.5)).unwrap();
 
     let mut air_sphere = Shape::sphere();
-    air_sphere.set_transform(Matrix4x4::scaling(0.5, 0.5, 0.5));
+    air_sphere.set_transform(Matrix4x4::scaling(0.5, 0.5, 0.5)).unwrap();
     let mut air_sphere_mat = air_sphere.material();
     air_sphere_mat.set_color(Pixel::white());
     air_sphere_mat.set_diffuse(0);
@@ -1951,7 +1950,7 @@ mod chapter11{
 
     w.set_objects(vec![wall,glass_sphere, air_sphere]);
 
-    let camera = Camera::with_transform(300, 300, 0.45, Matrix4x4::view(Tuple::point(0, 0, -5), Tuple::point(0, 0, 0), Tuple::vector(0, 1, 0)));
+    let camera = Camera::with_transform(300, 300, 0.45, Matrix4x4::view(Tuple::point(0, 0, -5), Tuple::point(0, 0, 0), Tuple::vector(0, 1, 0))).unwrap();
     let r = camera.ray_for_pixel(125, 125);
     let color = w.color_at(r, 5);
     println!("{}", color);
@@ -1965,4 +1964,2022 @@ mod chapter11{
         shape.set_material(mat);
         shape
     }
-}
\ No newline at end of file
+}
+mod shape_flags {
+    use crate::{Intersection, Light, Matrix4x4, Pixel, Ray, RayKind, Shape, ShapeFlags, Tuple, World};
+
+    #[test]
+    fn shadow_and_visibility_flags() {
+        {
+            let f = ShapeFlags::default();
+            assert!(f.casts_shadow());
+            assert!(f.receives_shadow());
+            assert!(f.visible_to(RayKind::Camera));
+            assert!(f.visible_to(RayKind::Reflection));
+            assert!(f.visible_to(RayKind::Refraction));
+        }
+
+        {
+            let mut w = World::default();
+            let p = Tuple::point(10, -10, 10);
+            assert!(w.is_shadowed(p) == Pixel::black());
+
+            let mut objs = w.objects();
+            for obj in objs.iter_mut() {
+                let mut f = obj.flags();
+                f.set_casts_shadow(false);
+                obj.set_flags(f);
+            }
+            w.set_objects(objs);
+            assert!(w.is_shadowed(p) == Pixel::white());
+        }
+
+        {
+            let mut w = World::new();
+            w.set_light(Light::point(Tuple::point(0, 0, -10), Pixel::white()));
+            let s1 = Shape::sphere();
+            let mut s2 = Shape::sphere();
+            s2.set_transform(Matrix4x4::translation(0, 0, 10)).unwrap();
+            let mut f = s2.flags();
+            f.set_receives_shadow(false);
+            s2.set_flags(f);
+            w.set_objects(vec![s1, s2.clone()]);
+
+            let r = Ray::new(Tuple::point(0, 0, 5), Tuple::vector(0, 0, 1));
+            let i = Intersection::new(4, s2);
+            let comps = i.prepare_computations(r, vec![i.clone()]);
+            assert!(w.is_shadowed(comps.over_point()) == Pixel::black());
+            assert!(w.shade_hit(comps, 5) == Pixel::rgb(1.9, 1.9, 1.9));
+        }
+
+        {
+            let mut w = World::default();
+            let mut objs = w.objects();
+            let mut f = objs[0].flags();
+            f.set_visible_to_camera(false);
+            objs[0].set_flags(f);
+            w.set_objects(objs);
+
+            let r = Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1));
+            assert!(w.intersect_world(r).len() == 4);
+            assert!(w.intersect_world_for(r, RayKind::Camera).len() == 2);
+            assert!(w.intersect_world_for(r, RayKind::Reflection).len() == 4);
+            assert!(w.color_at(r, 5) == w.color_at_for(r, RayKind::Camera, 5));
+            assert!(w.color_at(r, 5) != w.color_at_for(r, RayKind::Reflection, 5));
+        }
+    }
+}
+
+mod transparent_shadows {
+    use crate::{Light, Material, Matrix4x4, Pixel, Shape, Tuple, World};
+
+    #[test]
+    fn colored_shadow_transmission() {
+        {
+            let w = World::default();
+            assert!(!w.transparent_shadows());
+            assert!(w.is_shadowed(Tuple::point(10, -10, 10)) == Pixel::black());
+            assert!(w.is_shadowed(Tuple::point(0, 10, 0)) == Pixel::white());
+        }
+
+        {
+            let mut w = World::new();
+            w.set_light(Light::point(Tuple::point(0, 10, 0), Pixel::white()));
+            let mut glass = Shape::sphere();
+            glass.set_transform(Matrix4x4::translation(0, 5, 0)).unwrap();
+            let mut m = glass.material();
+            m.set_color(Pixel::rgb(1.0, 0.5, 0.5));
+            m.set_transparency(0.5);
+            glass.set_material(m);
+            w.set_objects(vec![glass]);
+
+            let p = Tuple::point(0, 0, 0);
+            assert!(w.is_shadowed(p) == Pixel::black());
+            w.set_transparent_shadows(true);
+            // Entering and leaving the glass filters the light only once.
+            assert!(w.is_shadowed(p) == Pixel::rgb(0.5, 0.25, 0.25));
+            assert!(w.is_shadowed(Tuple::point(0, 5, 0)) == Pixel::rgb(0.5, 0.25, 0.25));
+            assert!(w.is_shadowed(Tuple::point(5, 0, 0)) == Pixel::white());
+
+            let mut objs = w.objects();
+            let mut opaque = Shape::sphere();
+            opaque.set_transform(Matrix4x4::translation(0, 2, 0) * Matrix4x4::scaling(0.5, 0.5, 0.5)).unwrap();
+            objs.push(opaque);
+            w.set_objects(objs);
+            assert!(w.is_shadowed(p) == Pixel::black());
+        }
+
+        {
+            let m = Material::default();
+            let s = Shape::sphere();
+            let light = Light::point(Tuple::point(0, 0, -10), Pixel::white());
+            let position = Tuple::point(0, 0, 0);
+            let eyev = Tuple::vector(0, 0, -1);
+            let normalv = Tuple::vector(0, 0, -1);
+            let shadowed = m.lighting(&s, &light, position, eyev, normalv, true);
+            let lit = m.lighting(&s, &light, position, eyev, normalv, false);
+            assert!(m.lighting_with_transmission(&s, &light, position, eyev, normalv, Pixel::black()) == shadowed);
+            assert!(m.lighting_with_transmission(&s, &light, position, eyev, normalv, Pixel::white()) == lit);
+            assert!(
+                m.lighting_with_transmission(&s, &light, position, eyev, normalv, Pixel::rgb(0.5, 0.5, 0.5))
+                    == Pixel::rgb(1.0, 1.0, 1.0)
+            );
+        }
+    }
+}
+
+mod path_tracing {
+    use crate::{
+        Camera, Integrator, Light, Matrix4x4, PathTracer, Pixel, Ray, Shape, Tuple, World, PI,
+    };
+
+    #[test]
+    fn path_tracing_integrator() {
+        {
+            let w = World::new();
+            let tracer = PathTracer::new(1);
+            let r = Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1));
+            assert!(tracer.radiance(&w, r, 1) == Pixel::black());
+        }
+
+        {
+            // single bounce with a point light equals the Whitted diffuse term
+            let mut w = World::new();
+            w.set_light(Light::point(Tuple::point(-10, 10, -10), Pixel::white()));
+            let mut floor = Shape::plane();
+            let mut m = floor.material();
+            m.set_ambient(0);
+            m.set_specular(0);
+            m.set_color(Pixel::rgb(0.8, 0.5, 0.2));
+            floor.set_material(m);
+            w.set_objects(vec![floor]);
+
+            let mut tracer = PathTracer::new(1);
+            tracer.set_max_depth(1);
+            let r = Ray::new(Tuple::point(0, 1, -1), Tuple::vector(0, -1, 1).normalize());
+            let expected = w.color_at(r, 5);
+            assert!(expected != Pixel::black());
+            assert!(tracer.radiance(&w, r, 7) == expected);
+        }
+
+        {
+            let mut w = World::new();
+            let light = Light::area(
+                Tuple::point(-1, -1, 5),
+                Tuple::vector(2, 0, 0),
+                Tuple::vector(0, 2, 0),
+                Pixel::rgb(2.0, 1.0, 0.5),
+            );
+            w.set_light(light);
+            assert!(light.position() == Tuple::point(0, 0, 5));
+            let tracer = PathTracer::new(1);
+            let r = Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1));
+            assert!(tracer.radiance(&w, r, 3) == Pixel::rgb(2.0, 1.0, 0.5));
+            let r = Ray::new(Tuple::point(5, 0, -5), Tuple::vector(0, 0, 1));
+            assert!(tracer.radiance(&w, r, 3) == Pixel::black());
+        }
+
+        {
+            // a diffuse floor under a huge area light converges to albedo * radiance
+            let mut w = World::new();
+            w.set_light(Light::area(
+                Tuple::point(-1000, 1, -1000),
+                Tuple::vector(2000, 0, 0),
+                Tuple::vector(0, 0, 2000),
+                Pixel::white(),
+            ));
+            let mut floor = Shape::plane();
+            let mut m = floor.material();
+            m.set_diffuse(0.5);
+            m.set_specular(0);
+            floor.set_material(m);
+            w.set_objects(vec![floor]);
+
+            let mut tracer = PathTracer::new(1);
+            tracer.set_max_depth(2);
+            let r = Ray::new(Tuple::point(0, 0.5, -1), Tuple::vector(0, -1, 1).normalize());
+            let n = 4000;
+            let mut sum = 0.0;
+            for seed in 0..n {
+                sum += tracer.radiance(&w, r, seed).r();
+            }
+            let estimate = sum / n as f64;
+            assert!((estimate - 0.5).abs() < 0.01);
+        }
+
+        {
+            let w = World::default();
+            let c = Camera::with_transform(
+                11,
+                11,
+                PI / 2.0,
+                Matrix4x4::view(
+                    Tuple::point(0, 0, -5),
+                    Tuple::point(0, 0, 0),
+                    Tuple::vector(0, 1, 0),
+                ),
+            ).unwrap();
+            let whitted = c.render_with(World::default(), Integrator::whitted());
+            assert!(whitted.get(5, 5) == Pixel::rgb(0.38066, 0.47583, 0.2855));
+            let traced = c.render_with(w, Integrator::path_tracing(4));
+            assert!(traced.get(5, 5) != Pixel::black());
+            assert!(traced.get(0, 0) == Pixel::black());
+        }
+    }
+}
+
+mod ambient_occlusion {
+    use crate::{
+        equal, AmbientOcclusion, Camera, Intersection, Light, Matrix4x4, Pixel, Ray, Shape, Tuple,
+        World, PI,
+    };
+
+    #[test]
+    fn ambient_occlusion_estimator() {
+        let ao = AmbientOcclusion::new(64, 2);
+        assert!(ao.samples() == 64);
+        assert!(equal(ao.max_distance(), 2.0));
+
+        {
+            let mut w = World::new();
+            w.set_objects(vec![Shape::plane()]);
+            let open = w.ambient_occlusion_at(Tuple::point(0, 0.0001, 0), Tuple::vector(0, 1, 0), ao);
+            assert!(equal(open, 1.0));
+        }
+
+        {
+            let mut w = World::new();
+            let mut dome = Shape::sphere();
+            dome.set_transform(Matrix4x4::scaling(1.5, 1.5, 1.5)).unwrap();
+            w.set_objects(vec![Shape::plane(), dome]);
+            let p = Tuple::point(0, 0.0001, 0);
+            let n = Tuple::vector(0, 1, 0);
+            assert!(equal(w.ambient_occlusion_at(p, n, ao), 0.0));
+            assert!(equal(w.ambient_occlusion_at(p, n, AmbientOcclusion::new(64, 1)), 1.0));
+        }
+
+        {
+            let mut w = World::new();
+            let mut wall = Shape::plane();
+            wall.set_transform(Matrix4x4::translation(0, 0, 0.5) * Matrix4x4::rotation_x(PI / 2.0)).unwrap();
+            w.set_objects(vec![Shape::plane(), wall]);
+            let open = w.ambient_occlusion_at(Tuple::point(0, 0.0001, 0), Tuple::vector(0, 1, 0), AmbientOcclusion::new(256, 100));
+            assert!(open > 0.3 && open < 0.7);
+        }
+
+        {
+            let mut w = World::new();
+            w.set_light(Light::point(Tuple::point(0, 10, 0), Pixel::white()));
+            let mut floor = Shape::plane();
+            let mut m = floor.material();
+            m.set_ambient(1);
+            m.set_diffuse(0);
+            m.set_specular(0);
+            floor.set_material(m);
+            let mut dome = Shape::sphere();
+            dome.set_transform(Matrix4x4::scaling(1.5, 1.5, 1.5)).unwrap();
+            w.set_objects(vec![floor.clone(), dome]);
+
+            let r = Ray::new(Tuple::point(0, 1, 0), Tuple::vector(0, -1, 0));
+            let i = Intersection::new(1, floor);
+            let comps = i.prepare_computations(r, vec![i.clone()]);
+            assert!(w.shade_hit(comps.clone(), 5) == Pixel::white());
+            w.set_ambient_occlusion(Some(ao));
+            assert!(w.shade_hit(comps, 5) == Pixel::black());
+        }
+
+        {
+            let w = World::default();
+            let c = Camera::with_transform(
+                11,
+                11,
+                PI / 2.0,
+                Matrix4x4::view(Tuple::point(0, 0, -5), Tuple::point(0, 0, 0), Tuple::vector(0, 1, 0)),
+            ).unwrap();
+            let image = c.render_ambient_occlusion(w, ao);
+            assert!(image.get(0, 0) == Pixel::white());
+            assert!(image.get(5, 5) == Pixel::white());
+        }
+    }
+}
+
+mod photon_mapping {
+    use crate::{
+        Intersection, Light, Matrix4x4, Photon, PhotonMap, Pixel, Ray, Shape, Tuple, World, PI,
+    };
+
+    #[test]
+    fn caustic_photon_map() {
+        {
+            let mut photons = vec![];
+            for i in 0..500 {
+                let x = ((i * 37) % 101) as f64 / 10.0;
+                let y = ((i * 53) % 97) as f64 / 10.0;
+                let z = ((i * 71) % 89) as f64 / 10.0;
+                photons.push(Photon::new(Tuple::point(x, y, z), Tuple::vector(0, -1, 0), Pixel::white()));
+            }
+            let map = PhotonMap::new(photons.clone());
+            assert!(map.len() == 500);
+
+            let p = Tuple::point(4.2, 5.1, 3.3);
+            let found = map.nearest(p, 10, 100.0);
+            let mut brute: Vec<f64> = photons
+                .iter()
+                .map(|ph| {
+                    let d = ph.position() - p;
+                    d.dot(&d)
+                })
+                .collect();
+            brute.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            assert!(found.len() == 10);
+            for (i, (d, _)) in found.iter().enumerate() {
+                assert!((d - brute[i]).abs() < 1e-9);
+            }
+            assert!(map.nearest(p, 10, 0.01).is_empty());
+        }
+
+        {
+            let mut photons = vec![];
+            for x in -50..=50 {
+                for z in -50..=50 {
+                    let position = Tuple::point(x as f64 / 10.0, 0, z as f64 / 10.0);
+                    photons.push(Photon::new(position, Tuple::vector(0, -1, 0), Pixel::white()));
+                }
+            }
+            let mut map = PhotonMap::new(photons);
+            map.set_gather_count(100);
+            map.set_gather_radius(1);
+            let e = map.irradiance(Tuple::point(0, 0, 0), Tuple::vector(0, 1, 0));
+            assert!((e.r() - 100.0).abs() < 15.0);
+            let e = map.irradiance(Tuple::point(0, 0, 0), Tuple::vector(0, -1, 0));
+            assert!(e == Pixel::black());
+        }
+
+        {
+            let mut w = World::new();
+            w.set_light(Light::point(Tuple::point(0, 10, 0), Pixel::white()));
+            let mut lens = Shape::sphere();
+            lens.set_transform(Matrix4x4::translation(0, 1.8, 0)).unwrap();
+            let mut m = lens.material();
+            m.set_transparency(1.0);
+            m.set_refractive_index(1.5);
+            lens.set_material(m);
+            let floor = Shape::plane();
+            w.set_objects(vec![lens, floor.clone()]);
+
+            assert!(w.emit_caustic_photons(0).is_empty());
+            let mut map = w.emit_caustic_photons(20000);
+            assert!(!map.is_empty());
+            let mut focused_count = 0;
+            for photon in map.photons() {
+                let p = photon.position();
+                assert!(p.get_y().abs() < 0.001);
+                if p.get_x().powi(2) + p.get_z().powi(2) < 0.25 {
+                    focused_count += 1;
+                }
+            }
+            assert!(focused_count * 2 > map.len());
+
+            map.set_gather_radius(0.2);
+            let focused = map.irradiance(Tuple::point(0, 0, 0), Tuple::vector(0, 1, 0));
+            assert!(focused.r() > 10.0 * PI);
+
+            let r = Ray::new(Tuple::point(0, 1, -1), Tuple::vector(0, -1, 1).normalize());
+            let i = Intersection::new(2f64.sqrt(), floor.clone());
+            let comps = i.prepare_computations(r, vec![i.clone()]);
+            assert!(w.caustic_color(comps.clone()) == Pixel::black());
+            let plain = w.shade_hit(comps, 5);
+            w.set_caustics(Some(map));
+            let r = Ray::new(Tuple::point(0, 1, -0.001), Tuple::vector(0, -1, 0.001).normalize());
+            let i = Intersection::new(1.0, floor);
+            let comps = i.prepare_computations(r, vec![i.clone()]);
+            assert!(w.caustic_color(comps).r() > 1.0);
+            assert!(plain == Pixel::rgb(0.1, 0.1, 0.1));
+        }
+    }
+}
+
+mod chapter12 {
+    use crate::{equal, Ray, Shape, Tuple};
+
+    #[test]
+    fn intersecting_a_cube() {
+        let c = Shape::cube();
+        let cases = [
+            (Tuple::point(5, 0.5, 0), Tuple::vector(-1, 0, 0), 4.0, 6.0),
+            (Tuple::point(-5, 0.5, 0), Tuple::vector(1, 0, 0), 4.0, 6.0),
+            (Tuple::point(0.5, 5, 0), Tuple::vector(0, -1, 0), 4.0, 6.0),
+            (Tuple::point(0.5, -5, 0), Tuple::vector(0, 1, 0), 4.0, 6.0),
+            (Tuple::point(0.5, 0, 5), Tuple::vector(0, 0, -1), 4.0, 6.0),
+            (Tuple::point(0.5, 0, -5), Tuple::vector(0, 0, 1), 4.0, 6.0),
+            (Tuple::point(0, 0.5, 0), Tuple::vector(0, 0, 1), -1.0, 1.0),
+        ];
+        for (origin, direction, t1, t2) in cases {
+            let xs = c.intersect(Ray::new(origin, direction));
+            assert!(xs.len() == 2);
+            assert!(equal(xs[0].t(), t1));
+            assert!(equal(xs[1].t(), t2));
+        }
+
+        let misses = [
+            (Tuple::point(-2, 0, 0), Tuple::vector(0.2673, 0.5345, 0.8018)),
+            (Tuple::point(0, -2, 0), Tuple::vector(0.8018, 0.2673, 0.5345)),
+            (Tuple::point(0, 0, -2), Tuple::vector(0.5345, 0.8018, 0.2673)),
+            (Tuple::point(2, 0, 2), Tuple::vector(0, 0, -1)),
+            (Tuple::point(0, 2, 2), Tuple::vector(0, -1, 0)),
+            (Tuple::point(2, 2, 0), Tuple::vector(-1, 0, 0)),
+        ];
+        for (origin, direction) in misses {
+            let xs = c.intersect(Ray::new(origin, direction));
+            assert!(xs.is_empty());
+        }
+    }
+
+    #[test]
+    fn normal_on_a_cube() {
+        let c = Shape::cube();
+        let cases = [
+            (Tuple::point(1, 0.5, -0.8), Tuple::vector(1, 0, 0)),
+            (Tuple::point(-1, -0.2, 0.9), Tuple::vector(-1, 0, 0)),
+            (Tuple::point(-0.4, 1, -0.1), Tuple::vector(0, 1, 0)),
+            (Tuple::point(0.3, -1, -0.7), Tuple::vector(0, -1, 0)),
+            (Tuple::point(-0.6, 0.3, 1), Tuple::vector(0, 0, 1)),
+            (Tuple::point(0.4, 0.4, -1), Tuple::vector(0, 0, -1)),
+            (Tuple::point(1, 1, 1), Tuple::vector(1, 0, 0)),
+            (Tuple::point(-1, -1, -1), Tuple::vector(-1, 0, 0)),
+        ];
+        for (point, normal) in cases {
+            assert!(c.normal_at(point).unwrap() == normal);
+        }
+    }
+}
+
+mod chapter13 {
+    use crate::{equal, shape::Cylinder, Num, Ray, Shape, Tuple};
+
+    #[test]
+    fn cylinders() {
+        {
+            let cyl = Shape::cylinder();
+            let misses = [
+                (Tuple::point(1, 0, 0), Tuple::vector(0, 1, 0)),
+                (Tuple::point(0, 0, 0), Tuple::vector(0, 1, 0)),
+                (Tuple::point(0, 0, -5), Tuple::vector(1, 1, 1)),
+            ];
+            for (origin, direction) in misses {
+                let xs = cyl.intersect(Ray::new(origin, direction.normalize()));
+                assert!(xs.is_empty());
+            }
+        }
+
+        {
+            let cyl = Shape::cylinder();
+            let hits = [
+                (Tuple::point(1, 0, -5), Tuple::vector(0, 0, 1), 5.0, 5.0),
+                (Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1), 4.0, 6.0),
+                (Tuple::point(0.5, 0, -5), Tuple::vector(0.1, 1, 1), 6.80798, 7.08872),
+            ];
+            for (origin, direction, t0, t1) in hits {
+                let xs = cyl.intersect(Ray::new(origin, direction.normalize()));
+                assert!(xs.len() == 2);
+                assert!(equal(xs[0].t(), t0));
+                assert!(equal(xs[1].t(), t1));
+            }
+        }
+
+        {
+            let cyl = Shape::cylinder();
+            let normals = [
+                (Tuple::point(1, 0, 0), Tuple::vector(1, 0, 0)),
+                (Tuple::point(0, 5, -1), Tuple::vector(0, 0, -1)),
+                (Tuple::point(0, -2, 1), Tuple::vector(0, 0, 1)),
+                (Tuple::point(-1, 1, 0), Tuple::vector(-1, 0, 0)),
+            ];
+            for (point, normal) in normals {
+                assert!(cyl.normal_at(point).unwrap() == normal);
+            }
+        }
+
+        {
+            let cyl = Cylinder::new();
+            assert!(cyl.minimum() == Num::NEG_INFINITY);
+            assert!(cyl.maximum() == Num::INFINITY);
+            assert!(!cyl.closed());
+        }
+
+        {
+            let cyl = Shape::truncated_cylinder(1, 2, false);
+            let cases = [
+                (Tuple::point(0, 1.5, 0), Tuple::vector(0.1, 1, 0), 0),
+                (Tuple::point(0, 3, -5), Tuple::vector(0, 0, 1), 0),
+                (Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1), 0),
+                (Tuple::point(0, 2, -5), Tuple::vector(0, 0, 1), 0),
+                (Tuple::point(0, 1, -5), Tuple::vector(0, 0, 1), 0),
+                (Tuple::point(0, 1.5, -2), Tuple::vector(0, 0, 1), 2),
+            ];
+            for (origin, direction, count) in cases {
+                let xs = cyl.intersect(Ray::new(origin, direction.normalize()));
+                assert!(xs.len() == count);
+            }
+        }
+
+        {
+            let cyl = Shape::truncated_cylinder(1, 2, true);
+            let cases = [
+                (Tuple::point(0, 3, 0), Tuple::vector(0, -1, 0), 2),
+                (Tuple::point(0, 3, -2), Tuple::vector(0, -1, 2), 2),
+                (Tuple::point(0, 4, -2), Tuple::vector(0, -1, 1), 2),
+                (Tuple::point(0, 0, -2), Tuple::vector(0, 1, 2), 2),
+                (Tuple::point(0, -1, -2), Tuple::vector(0, 1, 1), 2),
+            ];
+            for (origin, direction, count) in cases {
+                let xs = cyl.intersect(Ray::new(origin, direction.normalize()));
+                assert!(xs.len() == count);
+            }
+
+            let normals = [
+                (Tuple::point(0, 1, 0), Tuple::vector(0, -1, 0)),
+                (Tuple::point(0.5, 1, 0), Tuple::vector(0, -1, 0)),
+                (Tuple::point(0, 1, 0.5), Tuple::vector(0, -1, 0)),
+                (Tuple::point(0, 2, 0), Tuple::vector(0, 1, 0)),
+                (Tuple::point(0.5, 2, 0), Tuple::vector(0, 1, 0)),
+                (Tuple::point(0, 2, 0.5), Tuple::vector(0, 1, 0)),
+            ];
+            for (point, normal) in normals {
+                assert!(cyl.normal_at(point).unwrap() == normal);
+            }
+        }
+    }
+
+    #[test]
+    fn double_napped_cones() {
+        {
+            let shape = Shape::cone();
+            let cases = [
+                (Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1), 5.0, 5.0),
+                (Tuple::point(0, 0, -5), Tuple::vector(1, 1, 1), 8.66025, 8.66025),
+                (Tuple::point(1, 1, -5), Tuple::vector(-0.5, -1, 1), 4.55006, 49.44994),
+            ];
+            for (origin, direction, t0, t1) in cases {
+                let xs = shape.intersect(Ray::new(origin, direction.normalize()));
+                assert!(xs.len() == 2);
+                assert!(equal(xs[0].t(), t0));
+                assert!(equal(xs[1].t(), t1));
+            }
+        }
+
+        {
+            let shape = Shape::cone();
+            let r = Ray::new(Tuple::point(0, 0, -1), Tuple::vector(0, 1, 1).normalize());
+            let xs = shape.intersect(r);
+            assert!(xs.len() == 1);
+            assert!(equal(xs[0].t(), 0.35355));
+
+            let r = Ray::new(Tuple::point(0, 0, 0), Tuple::vector(0, 1, 1).normalize());
+            assert!(shape.intersect(r).is_empty());
+        }
+
+        {
+            let shape = Shape::truncated_cone(-0.5, 0.5, true);
+            let cases = [
+                (Tuple::point(0, 0, -5), Tuple::vector(0, 1, 0), 0),
+                (Tuple::point(0, 0, -0.25), Tuple::vector(0, 1, 1), 2),
+                (Tuple::point(0, 0, -0.25), Tuple::vector(0, 1, 0), 4),
+            ];
+            for (origin, direction, count) in cases {
+                let xs = shape.intersect(Ray::new(origin, direction.normalize()));
+                assert!(xs.len() == count);
+            }
+        }
+
+        {
+            let shape = Shape::cone();
+            let normals = [
+                (Tuple::point(1, 1, 1), Tuple::vector(1, -(2f64.sqrt()), 1)),
+                (Tuple::point(-1, -1, 0), Tuple::vector(-1, 1, 0)),
+            ];
+            for (point, normal) in normals {
+                assert!(shape.normal_at(point).unwrap() == normal.normalize());
+            }
+            let apex = shape.normal_at(Tuple::point(0, 0, 0)).unwrap();
+            assert!(apex == Tuple::vector(0, 1, 0));
+        }
+
+        {
+            let shape = Shape::truncated_cone(-1, 1, true);
+            assert!(shape.normal_at(Tuple::point(0.5, 1, 0)).unwrap() == Tuple::vector(0, 1, 0));
+            assert!(shape.normal_at(Tuple::point(0, -1, 0.5)).unwrap() == Tuple::vector(0, -1, 0));
+        }
+    }
+}
+
+mod chapter14 {
+    use crate::{Matrix4x4, Ray, Shape, Tuple, PI};
+
+    #[test]
+    fn groups() {
+        {
+            let mut g = Shape::group();
+            assert!(g.transform() == Matrix4x4::identity());
+            assert!(g.children().is_empty());
+
+            let s = Shape::sphere();
+            g.add_child(s.clone()).unwrap();
+            assert!(g.children().len() == 1);
+            assert!(g.children()[0] == s);
+
+            // Only groups take children, and only their children have normals.
+            assert!(g.normal_at(Tuple::point(1, 0, 0)).is_none());
+            assert!(Shape::sphere().add_child(Shape::sphere()).is_err());
+        }
+
+        {
+            let g = Shape::group();
+            let r = Ray::new(Tuple::point(0, 0, 0), Tuple::vector(0, 0, 1));
+            assert!(g.intersect(r).is_empty());
+        }
+
+        {
+            let s1 = Shape::sphere();
+            let mut s2 = Shape::sphere();
+            s2.set_transform(Matrix4x4::translation(0, 0, -3)).unwrap();
+            let mut s3 = Shape::sphere();
+            s3.set_transform(Matrix4x4::translation(5, 0, 0)).unwrap();
+            let g = Shape::group_of(vec![s1.clone(), s2.clone(), s3]);
+
+            let r = Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1));
+            let xs = g.intersect(r);
+            assert!(xs.len() == 4);
+            assert!(xs[0].object() == s2);
+            assert!(xs[1].object() == s2);
+            assert!(xs[2].object() == s1);
+            assert!(xs[3].object() == s1);
+        }
+
+        {
+            let mut s = Shape::sphere();
+            s.set_transform(Matrix4x4::translation(5, 0, 0)).unwrap();
+            let mut g = Shape::group_of(vec![s]);
+            g.set_transform(Matrix4x4::scaling(2, 2, 2)).unwrap();
+
+            let r = Ray::new(Tuple::point(10, 0, -10), Tuple::vector(0, 0, 1));
+            assert!(g.intersect(r).len() == 2);
+        }
+    }
+
+    #[test]
+    fn transform_hierarchies() {
+        let mut s = Shape::sphere();
+        s.set_transform(Matrix4x4::translation(5, 0, 0)).unwrap();
+        let mut g2 = Shape::group_of(vec![s]);
+        g2.set_transform(Matrix4x4::scaling(1, 2, 3)).unwrap();
+        let mut g1 = Shape::group_of(vec![g2]);
+        g1.set_transform(Matrix4x4::rotation_y(PI / 2.0)).unwrap();
+
+        // A hit carries the leaf with the whole parent chain folded in.
+        let r = Ray::new(Tuple::point(10, 0, -10), Tuple::vector(0, 0, 1));
+        assert!(g1.intersect(r).is_empty());
+        let r = Ray::new(Tuple::point(-10, 0, -5), Tuple::vector(1, 0, 0));
+        let xs = g1.intersect(r);
+        assert!(xs.len() == 2);
+        let leaf = xs[0].object();
+        assert!(leaf.transform() == g1.transform() * g1.children()[0].transform() * Matrix4x4::translation(5, 0, 0));
+
+        let v = 3f64.sqrt() / 3.0;
+        let n = leaf.normal_to_world(Tuple::vector(v, v, v));
+        assert!(n == Tuple::vector(0.2857, 0.4286, -0.8571));
+
+        let n = leaf.normal_at(Tuple::point(1.7321, 1.1547, -5.5774)).unwrap();
+        assert!(n == Tuple::vector(0.2857, 0.4286, -0.8571));
+
+        let mut s = Shape::sphere();
+        s.set_transform(Matrix4x4::translation(5, 0, 0)).unwrap();
+        let mut g2 = Shape::group_of(vec![s]);
+        g2.set_transform(Matrix4x4::scaling(2, 2, 2)).unwrap();
+        let mut g1 = Shape::group_of(vec![g2]);
+        g1.set_transform(Matrix4x4::rotation_y(PI / 2.0)).unwrap();
+
+        let r = Ray::new(Tuple::point(-1, 0, -20), Tuple::vector(0, 0, 1));
+        let xs = g1.intersect(r);
+        assert!(xs.len() == 2);
+        let p = xs[0].object().world_to_object(Tuple::point(-2, 0, -10));
+        assert!(p == Tuple::point(0, 0, -1));
+    }
+
+    #[test]
+    fn group_materials() {
+        let mut g = Shape::group_of(vec![Shape::sphere(), Shape::cube()]);
+        let mut m = g.material();
+        m.set_ambient(1);
+        g.set_material(m);
+        assert!(g.children().iter().all(|child| child.material() == m));
+
+        // Children added later take the group's material too, while a group
+        // that never had one leaves its children's alone.
+        g.add_child(Shape::cylinder()).unwrap();
+        assert!(g.children().len() == 3 && g.children().iter().all(|child| child.material() == m));
+        let mut plain = Shape::group();
+        let mut own = Shape::sphere();
+        own.set_material(m);
+        plain.add_child(own).unwrap();
+        assert!(plain.children()[0].material() == m);
+    }
+}
+
+mod chapter15 {
+    use crate::{equal, Intersection, ObjParser, Ray, Shape, Tuple};
+
+    #[test]
+    fn triangles() {
+        {
+            let p1 = Tuple::point(0, 1, 0);
+            let p2 = Tuple::point(-1, 0, 0);
+            let p3 = Tuple::point(1, 0, 0);
+            let t = Shape::triangle(p1, p2, p3);
+            if let Shape::Triangle(tri) = t {
+                assert!(tri.p1() == p1);
+                assert!(tri.p2() == p2);
+                assert!(tri.p3() == p3);
+                assert!(tri.e1() == Tuple::vector(-1, -1, 0));
+                assert!(tri.e2() == Tuple::vector(1, -1, 0));
+                assert!(tri.normal() == Tuple::vector(0, 0, -1));
+            } else {
+                panic!("expected a triangle");
+            }
+
+            assert!(t.normal_at(Tuple::point(0, 0.5, 0)).unwrap() == Tuple::vector(0, 0, -1));
+            assert!(t.normal_at(Tuple::point(-0.5, 0.75, 0)).unwrap() == Tuple::vector(0, 0, -1));
+            assert!(t.normal_at(Tuple::point(0.5, 0.25, 0)).unwrap() == Tuple::vector(0, 0, -1));
+        }
+
+        {
+            let t = Shape::triangle(Tuple::point(0, 1, 0), Tuple::point(-1, 0, 0), Tuple::point(1, 0, 0));
+            let misses = [
+                (Tuple::point(0, -1, -2), Tuple::vector(0, 1, 0)),
+                (Tuple::point(1, 1, -2), Tuple::vector(0, 0, 1)),
+                (Tuple::point(-1, 1, -2), Tuple::vector(0, 0, 1)),
+                (Tuple::point(0, -1, -2), Tuple::vector(0, 0, 1)),
+            ];
+            for (origin, direction) in misses {
+                assert!(t.intersect(Ray::new(origin, direction)).is_empty());
+            }
+
+            let xs = t.intersect(Ray::new(Tuple::point(0, 0.5, -2), Tuple::vector(0, 0, 1)));
+            assert!(xs.len() == 1);
+            assert!(equal(xs[0].t(), 2.0));
+        }
+    }
+
+    #[test]
+    fn smooth_triangles() {
+        let tri = Shape::smooth_triangle(
+            Tuple::point(0, 1, 0),
+            Tuple::point(-1, 0, 0),
+            Tuple::point(1, 0, 0),
+            Tuple::vector(0, 1, 0),
+            Tuple::vector(-1, 0, 0),
+            Tuple::vector(1, 0, 0),
+        );
+
+        if let Shape::SmoothTriangle(t) = tri {
+            assert!(t.n1() == Tuple::vector(0, 1, 0));
+            assert!(t.n2() == Tuple::vector(-1, 0, 0));
+            assert!(t.n3() == Tuple::vector(1, 0, 0));
+        } else {
+            panic!("expected a smooth triangle");
+        }
+
+        {
+            let s = Shape::triangle(Tuple::point(0, 1, 0), Tuple::point(-1, 0, 0), Tuple::point(1, 0, 0));
+            let i = Intersection::with_uv(3.5, s, 0.2, 0.4);
+            assert!(equal(i.u(), 0.2));
+            assert!(equal(i.v(), 0.4));
+        }
+
+        {
+            let r = Ray::new(Tuple::point(-0.2, 0.3, -2), Tuple::vector(0, 0, 1));
+            let xs = tri.intersect(r);
+            assert!(equal(xs[0].u(), 0.45));
+            assert!(equal(xs[0].v(), 0.25));
+        }
+
+        {
+            let i = Intersection::with_uv(1, tri.clone(), 0.45, 0.25);
+            let n = tri.normal_at_hit(Tuple::point(0, 0, 0), &i).unwrap();
+            assert!(n == Tuple::vector(-0.5547, 0.83205, 0));
+        }
+
+        {
+            let i = Intersection::with_uv(1, tri, 0.45, 0.25);
+            let r = Ray::new(Tuple::point(-0.2, 0.3, -2), Tuple::vector(0, 0, 1));
+            let comps = i.prepare_computations(r, vec![i.clone()]);
+            assert!(comps.normalv() == Tuple::vector(-0.5547, 0.83205, 0));
+        }
+    }
+
+    #[test]
+    fn obj_files() {
+        {
+            let source = "There was a young lady named Bright\nwho traveled much faster than light.\n\n# a comment\nShe set out one day\nin a relative way,\nand came back the previous night.\n";
+            let parser = ObjParser::parse(source).unwrap();
+            assert!(parser.skipped_lines() == 5);
+            assert!(parser.vertices().is_empty());
+        }
+
+        {
+            let source = "v -1 1 0\nv -1.0000 0.5000 0.0000\nv 1 0 0\nv 1 1 0\n\nf 1 2 3\nf 1 3 4\n";
+            let parser = ObjParser::parse(source).unwrap();
+            assert!(parser.vertices().len() == 4);
+            assert!(parser.vertices()[1] == Tuple::point(-1, 0.5, 0));
+
+            let g = parser.default_group();
+            assert!(g.len() == 2);
+            assert!(g[0] == Shape::triangle(Tuple::point(-1, 1, 0), Tuple::point(-1, 0.5, 0), Tuple::point(1, 0, 0)));
+            assert!(g[1] == Shape::triangle(Tuple::point(-1, 1, 0), Tuple::point(1, 0, 0), Tuple::point(1, 1, 0)));
+        }
+
+        {
+            // A pentagon becomes a fan of three triangles around its first vertex.
+            let source = "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\nv 0 2 0\n\nf 1 2 3 4 5\n";
+            let parser = ObjParser::parse(source).unwrap();
+            let g = parser.default_group();
+            assert!(g.len() == 3);
+            assert!(g[2] == Shape::triangle(Tuple::point(-1, 1, 0), Tuple::point(1, 1, 0), Tuple::point(0, 2, 0)));
+        }
+
+        {
+            let source = "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\ng FirstGroup\nf 1 2 3\ng SecondGroup\nf 1 3 4\n";
+            let parser = ObjParser::parse(source).unwrap();
+            assert!(parser.group_names() == vec!["FirstGroup", "SecondGroup"]);
+            assert!(parser.group("FirstGroup").unwrap().len() == 1);
+            assert!(parser.group("SecondGroup").unwrap()[0] == Shape::triangle(Tuple::point(-1, 1, 0), Tuple::point(1, 0, 0), Tuple::point(1, 1, 0)));
+            assert!(parser.group("Missing").is_none());
+
+            let g = parser.to_group();
+            assert!(g.children().len() == 2);
+            assert!(g.children()[0].children()[0] == parser.group("FirstGroup").unwrap()[0]);
+        }
+
+        {
+            let source = "v 0 1 0\nv -1 0 0\nv 1 0 0\nvt 0 0\nvn -1 0 0\nvn 1 0 0\nvn 0 1 0\nf 1//3 2//1 3//2\nf 1/1/3 2/1/1 3/1/2\nf -3 -2 -1\n";
+            let parser = ObjParser::parse(source).unwrap();
+            assert!(parser.normals()[1] == Tuple::vector(1, 0, 0));
+            assert!(parser.texture_coords().len() == 1);
+            let smooth = Shape::smooth_triangle(
+                Tuple::point(0, 1, 0), Tuple::point(-1, 0, 0), Tuple::point(1, 0, 0),
+                Tuple::vector(0, 1, 0), Tuple::vector(-1, 0, 0), Tuple::vector(1, 0, 0),
+            );
+            let g = parser.default_group();
+            assert!(g[0] == smooth);
+            assert!(g[1] == smooth);
+            assert!(g[2] == Shape::triangle(Tuple::point(0, 1, 0), Tuple::point(-1, 0, 0), Tuple::point(1, 0, 0)));
+        }
+
+        {
+            let bad = [
+                ("v 1 2\n", "line 1:"),
+                ("v 0 0 0\nv 1 x 0\n", "line 2:"),
+                ("v 0 0 0\nv 1 0 0\nv 0 1 0\n\nf 1 2 4\n", "line 5:"),
+                ("v 0 0 0\nf 1 0 1\n", "line 2:"),
+                ("v 0 0 0\nv 1 0 0\nf 1 2\n", "line 3:"),
+                ("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1//1 2//1 3//1\n", "line 4:"),
+            ];
+            for (source, prefix) in bad {
+                let err = ObjParser::parse(source).err().unwrap();
+                assert!(err.starts_with(prefix), "{}", err);
+            }
+        }
+    }
+}
+
+mod chapter16 {
+    use crate::{equal, CsgOp, Matrix4x4, Ray, Shape, Tuple};
+
+    #[test]
+    fn csg_rules() {
+        let rules = [
+            (CsgOp::Union, [true, false, true, false, true, true, false, false]),
+            (CsgOp::Intersection, [false, true, false, true, false, false, true, true]),
+            (CsgOp::Difference, [true, false, true, false, false, false, true, true]),
+        ];
+        for (op, expected) in rules {
+            for (n, allowed) in expected.iter().enumerate() {
+                let (lhit, inl, inr) = (n < 4, n % 4 >= 2, n % 2 == 1);
+                assert!(op.intersection_allowed(lhit, inl, inr) == *allowed);
+            }
+        }
+    }
+
+    #[test]
+    fn csg_shapes() {
+        {
+            let s1 = Shape::sphere();
+            let s2 = Shape::cube();
+            let c = Shape::csg(CsgOp::Union, s1.clone(), s2.clone());
+            if let Shape::Csg(csg) = &c {
+                assert!(csg.operation() == CsgOp::Union);
+                assert!(*csg.left() == s1);
+                assert!(*csg.right() == s2);
+            } else {
+                panic!("expected a CSG shape");
+            }
+
+            let r = Ray::new(Tuple::point(0, 2, -5), Tuple::vector(0, 0, 1));
+            assert!(c.intersect(r).is_empty());
+            assert!(c.normal_at(Tuple::point(1, 0, 0)).is_none());
+        }
+
+        {
+            let s1 = Shape::sphere();
+            let mut s2 = Shape::sphere();
+            s2.set_transform(Matrix4x4::translation(0, 0, 0.5)).unwrap();
+            let r = Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1));
+
+            let xs = Shape::csg(CsgOp::Union, s1.clone(), s2.clone()).intersect(r);
+            assert!(xs.len() == 2);
+            assert!(equal(xs[0].t(), 4.0) && xs[0].object() == s1);
+            assert!(equal(xs[1].t(), 6.5) && xs[1].object() == s2);
+
+            let xs = Shape::csg(CsgOp::Intersection, s1.clone(), s2.clone()).intersect(r);
+            assert!(xs.len() == 2);
+            assert!(equal(xs[0].t(), 4.5) && xs[0].object() == s2);
+            assert!(equal(xs[1].t(), 6.0) && xs[1].object() == s1);
+
+            let xs = Shape::csg(CsgOp::Difference, s1.clone(), s2.clone()).intersect(r);
+            assert!(xs.len() == 2);
+            assert!(equal(xs[0].t(), 4.0) && xs[0].object() == s1);
+            assert!(equal(xs[1].t(), 4.5) && xs[1].object() == s2);
+        }
+
+        {
+            let mut s = Shape::sphere();
+            s.set_transform(Matrix4x4::translation(0, 0, 0.5)).unwrap();
+            let mut c = Shape::csg(CsgOp::Union, Shape::sphere(), s);
+            c.set_transform(Matrix4x4::translation(3, 0, 0)).unwrap();
+            let r = Ray::new(Tuple::point(3, 0, -5), Tuple::vector(0, 0, 1));
+            let xs = c.intersect(r);
+            assert!(xs.len() == 2);
+            assert!(xs[0].object().normal_at(r.position(xs[0].t())).unwrap() == Tuple::vector(0, 0, -1));
+        }
+    }
+
+    #[test]
+    fn csg_refraction() {
+        // A glass ball with an air bubble carved out of its middle.
+        let mut glass = Shape::sphere();
+        let mut m = glass.material();
+        m.set_transparency(1);
+        m.set_refractive_index(1.5);
+        glass.set_material(m);
+        let mut bubble = Shape::sphere();
+        bubble.set_transform(Matrix4x4::scaling(0.5, 0.5, 0.5)).unwrap();
+        let c = Shape::csg(CsgOp::Difference, glass, bubble);
+
+        let r = Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1));
+        let xs = c.intersect(r);
+        assert!(xs.len() == 4);
+        let expected = [(1.0, 1.5), (1.5, 1.0), (1.0, 1.5), (1.5, 1.0)];
+        for (i, (n1, n2)) in expected.iter().enumerate() {
+            let comps = xs[i].prepare_computations(r, xs.clone());
+            assert!(equal(comps.n1(), *n1));
+            assert!(equal(comps.n2(), *n2));
+        }
+    }
+}
+
+mod mesh_import {
+    use crate::{Material, Matrix4x4, Pixel, PlyParser, Ray, Shape, StlParser, Tuple};
+
+    const ASCII_STL: &str = "solid wedge
+  facet normal 0 0 -1
+    outer loop
+      vertex 0 1 0
+      vertex -1 0 0
+      vertex 1 0 0
+    endloop
+  endfacet
+  facet normal 0 0 -1
+    outer loop
+      vertex 0 1 0
+      vertex 1 0 0
+      vertex 1 1 0
+    endloop
+  endfacet
+endsolid wedge
+";
+
+    fn binary_stl(triangles: &[[f32; 9]]) -> Vec<u8> {
+        let mut bytes = b"solid but actually binary".to_vec();
+        bytes.resize(80, 0);
+        bytes.extend((triangles.len() as u32).to_le_bytes());
+        for t in triangles {
+            bytes.extend([0f32; 3].iter().chain(t.iter()).flat_map(|f| f.to_le_bytes()));
+            bytes.extend([0, 0]);
+        }
+        bytes
+    }
+
+    #[test]
+    fn stl_files() {
+        {
+            let parser = StlParser::parse(ASCII_STL.as_bytes()).unwrap();
+            assert!(parser.name() == "wedge");
+            assert!(parser.triangles().len() == 2);
+            assert!(parser.triangles()[1] == Shape::triangle(Tuple::point(0, 1, 0), Tuple::point(1, 0, 0), Tuple::point(1, 1, 0)));
+        }
+
+        {
+            let bytes = binary_stl(&[[0.0, 1.0, 0.0, -1.0, 0.0, 0.0, 1.0, 0.0, 0.0]]);
+            let parser = StlParser::parse(&bytes).unwrap();
+            assert!(parser.name() == "solid but actually binary");
+            assert!(parser.triangles() == [Shape::triangle(Tuple::point(0, 1, 0), Tuple::point(-1, 0, 0), Tuple::point(1, 0, 0))]);
+
+            let err = StlParser::parse(&bytes[..bytes.len() - 10]).err().unwrap();
+            assert!(err.starts_with("header declares 1 triangles"), "{}", err);
+
+            // A huge count in a tiny file is rejected rather than allocated.
+            let mut bytes = bytes;
+            bytes[80..84].copy_from_slice(&u32::MAX.to_le_bytes());
+            assert!(StlParser::parse(&bytes).is_err());
+        }
+
+        {
+            // Meshes transform and take materials like any other shape.
+            let mut mesh = StlParser::parse(ASCII_STL.as_bytes()).unwrap().to_group();
+            mesh.set_transform(Matrix4x4::translation(0, 0, 5)).unwrap();
+            let mut m = Material::default();
+            m.set_color(Pixel::red());
+            mesh.set_material(m);
+
+            let xs = mesh.intersect(Ray::new(Tuple::point(0, 0.5, 0), Tuple::vector(0, 0, 1)));
+            assert!(xs.len() == 1);
+            assert!(xs[0].t() == 5.0);
+            assert!(xs[0].object().material() == m);
+        }
+
+        {
+            let err = StlParser::parse(b"solid x\n facet normal 0 0 1\n  outer loop\n   vertex 0 0\n").err().unwrap();
+            assert!(err.starts_with("line 4:"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn ply_files() {
+        {
+            let source = "ply
+format ascii 1.0
+comment a colored quad
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+-1 0 0 255 0 0
+1 0 0 0 255 0
+1 1 0 0 0 255
+-1 1 0 255 255 255
+4 0 1 2 3
+";
+            let parser = PlyParser::parse(source.as_bytes()).unwrap();
+            assert!(parser.vertices().len() == 4);
+            assert!(parser.normals().is_empty());
+            assert!(parser.colors()[1] == Pixel::green());
+            assert!(parser.faces() == [vec![0, 1, 2, 3]]);
+            assert!(parser.triangles().len() == 2);
+
+            // Vertex colors replace the material color across the face.
+            let t = &parser.triangles()[0];
+            let m = t.material();
+            assert!(m.color_at(t, Tuple::point(-1, 0, 0)) == Pixel::red());
+            assert!(m.color_at(t, Tuple::point(0, 0, 0)) == Pixel::rgb(0.5, 0.5, 0.0));
+            assert!(m.color_at(t, Tuple::point(1, 1, 0)) == Pixel::blue());
+            assert!(Shape::sphere().set_vertex_colors(Pixel::red(), Pixel::green(), Pixel::blue()).is_err());
+
+            let err = PlyParser::parse(source.replace("0 0 255\n", "0 0\n").as_bytes()).err().unwrap();
+            assert!(err.starts_with("line 16:"), "{}", err);
+            let err = PlyParser::parse(source.replace("4 0 1 2 3", "3 0 1 7").as_bytes()).err().unwrap();
+            assert!(err.starts_with("face 0:"), "{}", err);
+            let err = PlyParser::parse(source.replace("4 0 1 2 3", "3 0 -1 2").as_bytes()).err().unwrap();
+            assert!(err.starts_with("face 0:"), "{}", err);
+            let err = PlyParser::parse(source.replace("4 0 1 2 3", "3 0 1.5 2").as_bytes()).err().unwrap();
+            assert!(err.starts_with("face 0:"), "{}", err);
+            let err = PlyParser::parse(source.replace("4 0 1 2 3", "2.5 0 1 2").as_bytes()).err().unwrap();
+            assert!(err.starts_with("line 18:"), "{}", err);
+            let err = PlyParser::parse(source.replace("property float y", "property list uchar float y").as_bytes()).err().unwrap();
+            assert!(err.contains("'y' cannot be a list"), "{}", err);
+        }
+
+        {
+            let mut bytes = b"ply\nformat binary_big_endian 1.0\nelement vertex 3\nproperty double x\nproperty double y\nproperty double z\nproperty float nx\nproperty float ny\nproperty float nz\nelement face 1\nproperty list uchar uint vertex_index\nend_header\n".to_vec();
+            let vertices = [([0.0, 1.0, 0.0], [0.0, 1.0, 0.0]), ([-1.0, 0.0, 0.0], [-1.0, 0.0, 0.0]), ([1.0, 0.0, 0.0], [1.0, 0.0, 0.0])];
+            for (p, n) in vertices {
+                bytes.extend(p.iter().flat_map(|f: &f64| f.to_be_bytes()));
+                bytes.extend(n.iter().flat_map(|f: &f64| (*f as f32).to_be_bytes()));
+            }
+            bytes.push(3);
+            bytes.extend([0u32, 1, 2].iter().flat_map(|i| i.to_be_bytes()));
+
+            let parser = PlyParser::parse(&bytes).unwrap();
+            let smooth = Shape::smooth_triangle(
+                Tuple::point(0, 1, 0), Tuple::point(-1, 0, 0), Tuple::point(1, 0, 0),
+                Tuple::vector(0, 1, 0), Tuple::vector(-1, 0, 0), Tuple::vector(1, 0, 0),
+            );
+            assert!(parser.triangles() == [smooth]);
+
+            let err = PlyParser::parse(&bytes[..bytes.len() - 2]).err().unwrap();
+            assert!(err.starts_with("face 0:"), "{}", err);
+
+            // Element counts far beyond the data run out of input instead of
+            // allocating up front.
+            let at = bytes.windows(14).position(|w| w == b"element face 1").unwrap();
+            let mut huge = bytes.clone();
+            huge.splice(at..at + 14, b"element face 4000000000".iter().copied());
+            let err = PlyParser::parse(&huge).err().unwrap();
+            assert!(err.starts_with("face 1:"), "{}", err);
+
+            // Nor can an element that takes no bytes per row.
+            let mut empty = bytes.clone();
+            empty.splice(at..at, b"element empty 400000000\n".iter().copied());
+            let err = PlyParser::parse(&empty).err().unwrap();
+            assert!(err.contains("'empty' has no properties"), "{}", err);
+        }
+    }
+}
+
+mod bounding_volumes {
+    use crate::{
+        sampler::Sampler, Bounds, Camera, CsgOp, Intersection, Matrix4x4, Num, Ray, Shape, Tuple, World, PI,
+    };
+
+    fn same_hits(a: &[Intersection], b: &[Intersection]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.t() == y.t() && x.object() == y.object())
+    }
+
+    fn random_scene(count: usize) -> Vec<Shape> {
+        let mut rng = Sampler::new(7);
+        let mut objects = vec![];
+        for i in 0..count {
+            let (x, y, z) = (rng.next() * 20.0 - 10.0, rng.next() * 20.0 - 10.0, rng.next() * 20.0 - 10.0);
+            if i % 3 == 0 {
+                let mut s = Shape::sphere();
+                s.set_transform(Matrix4x4::translation(x, y, z) * Matrix4x4::scaling(0.3, 0.6, 0.3)).unwrap();
+                objects.push(s);
+            } else {
+                let p = Tuple::point(x, y, z);
+                let q = Tuple::vector(rng.next(), rng.next(), rng.next());
+                objects.push(Shape::triangle(p, p + Tuple::vector(0.7, 0.1, 0.3), p + q));
+            }
+        }
+        objects
+    }
+
+    #[test]
+    fn shape_bounds() {
+        let unit = Bounds::new(Tuple::point(-1, -1, -1), Tuple::point(1, 1, 1));
+        assert!(Shape::sphere().bounds() == unit);
+        assert!(Shape::cube().bounds() == unit);
+        assert!(!Shape::plane().bounds().is_finite());
+        assert!(!Shape::cylinder().bounds().is_finite());
+        assert!(Shape::truncated_cylinder(-2, 3, true).bounds() == Bounds::new(Tuple::point(-1, -2, -1), Tuple::point(1, 3, 1)));
+        assert!(Shape::truncated_cone(-5, 3, true).bounds() == Bounds::new(Tuple::point(-5, -5, -5), Tuple::point(5, 3, 5)));
+        let t = Shape::triangle(Tuple::point(-3, 7, 2), Tuple::point(6, 2, -4), Tuple::point(2, -1, -1));
+        assert!(t.bounds() == Bounds::new(Tuple::point(-3, -1, -4), Tuple::point(6, 7, 2)));
+
+        let mut s = Shape::sphere();
+        s.set_transform(Matrix4x4::translation(1, -3, 5) * Matrix4x4::scaling(0.5, 2, 4)).unwrap();
+        assert!(s.world_bounds() == Bounds::new(Tuple::point(0.5, -5, 1), Tuple::point(1.5, -1, 9)));
+
+        let mut c = Shape::cube();
+        c.set_transform(Matrix4x4::rotation_y(PI / 4.0)).unwrap();
+        let r = 2f64.sqrt();
+        assert!(c.world_bounds() == Bounds::new(Tuple::point(-r, -1, -r), Tuple::point(r, 1, r)));
+
+        let mut cyl = Shape::truncated_cylinder(-2, 2, true);
+        cyl.set_transform(Matrix4x4::translation(-4, -1, 4) * Matrix4x4::scaling(0.5, 1, 0.5)).unwrap();
+        let g = Shape::group_of(vec![s.clone(), cyl]);
+        assert!(g.bounds() == Bounds::new(Tuple::point(-4.5, -5, 1), Tuple::point(1.5, 1, 9)));
+
+        let d = Shape::csg(CsgOp::Difference, s.clone(), Shape::cube());
+        assert!(d.bounds() == s.world_bounds());
+        assert!(!Shape::group_of(vec![s, Shape::plane()]).bounds().is_finite());
+
+        let b = Bounds::new(Tuple::point(5, -2, 0), Tuple::point(11, 4, 7));
+        assert!(b.intersects(Ray::new(Tuple::point(15, 1, 2), Tuple::vector(-1, 0, 0))));
+        assert!(b.intersects(Ray::new(Tuple::point(8, 2, 12), Tuple::vector(0, 0, 1))));
+        assert!(!b.intersects(Ray::new(Tuple::point(9, -1, -8), Tuple::vector(2, 4, 6))));
+        assert!(!b.intersects(Ray::new(Tuple::point(12, 5, 4), Tuple::vector(0, 0, 1))));
+    }
+
+    #[test]
+    fn bvh_matches_brute_force() {
+        let objects = random_scene(300);
+        let mut world = World::new();
+        world.set_objects(objects.clone());
+        let mut mesh = Shape::group_of(objects.clone());
+        mesh.set_transform(Matrix4x4::rotation_y(0.3)).unwrap();
+        let inv = mesh.transform().inverse().unwrap();
+
+        let mut rng = Sampler::new(11);
+        for _ in 0..100 {
+            let origin = Tuple::point(rng.next() * 30.0 - 15.0, rng.next() * 30.0 - 15.0, -20);
+            let target = Tuple::point(rng.next() * 16.0 - 8.0, rng.next() * 16.0 - 8.0, rng.next() * 16.0 - 8.0);
+            let ray = Ray::new(origin, (target - origin).normalize());
+
+            let brute_force = |ray: Ray| {
+                let mut xs: Vec<Intersection> = objects.iter().flat_map(|o| o.intersect(ray)).collect();
+                xs.sort_by(|a, b| a.t().partial_cmp(&b.t()).unwrap());
+                xs
+            };
+            assert!(same_hits(&world.intersect_world(ray), &brute_force(ray)));
+
+            let in_mesh = brute_force(ray.transform(inv));
+            let xs = mesh.intersect(ray);
+            assert!(xs.len() == in_mesh.len() && xs.iter().zip(&in_mesh).all(|(a, b)| a.t() == b.t()));
+        }
+    }
+
+    #[test]
+    fn bvh_keeps_unbounded_shapes() {
+        let mut world = World::new();
+        let mut far = Shape::sphere();
+        far.set_transform(Matrix4x4::translation(0, 0, 100)).unwrap();
+        world.set_objects(vec![Shape::plane(), far]);
+        let xs = world.intersect_world(Ray::new(Tuple::point(0, 1, -5), Tuple::vector(0, -1, 0)));
+        assert!(xs.len() == 1 && xs[0].object() == Shape::plane());
+        let xs = world.intersect_world(Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1)));
+        assert!(xs.len() == 2);
+        assert!((xs[0].t() - 104.0 as Num).abs() < 1e-9);
+    }
+
+    #[test]
+    fn bounds_queries() {
+        {
+            let b = Shape::plane().world_bounds();
+            assert!(!b.is_finite());
+            // Tuple equality is approximate, which infinities never pass.
+            let (min, max) = (b.min(), b.max());
+            assert!(min.get_x() == Num::NEG_INFINITY && min.get_y() == 0.0 && min.get_z() == Num::NEG_INFINITY);
+            assert!(max.get_x() == Num::INFINITY && max.get_y() == 0.0 && max.get_z() == Num::INFINITY);
+
+            let mut p = Shape::plane();
+            p.set_transform(Matrix4x4::translation(1, 2, 3)).unwrap();
+            assert!(p.world_bounds().min().get_y() == 2.0 && p.world_bounds().max().get_y() == 2.0);
+            p.set_transform(Matrix4x4::rotation_x(PI / 4.0)).unwrap();
+            let b = p.world_bounds();
+            assert!([b.min().get_x(), b.min().get_y(), b.min().get_z()].iter().all(|v| *v == Num::NEG_INFINITY));
+            assert!([b.max().get_x(), b.max().get_y(), b.max().get_z()].iter().all(|v| *v == Num::INFINITY));
+        }
+
+        {
+            let b = Bounds::new(Tuple::point(-1, -2, -3), Tuple::point(3, 2, 1));
+            assert!(b.size() == Tuple::vector(4, 4, 4));
+            assert!(b.centroid() == Tuple::point(1, 0, -1));
+            assert!(b.contains_point(Tuple::point(2, -2, 0)));
+            assert!(!b.contains_point(Tuple::point(2, -2, 2)));
+            assert!(Bounds::empty().is_empty() && Bounds::empty().size() == Tuple::vector(0, 0, 0));
+        }
+
+        {
+            let mut w = World::default();
+            assert!(w.bounds() == Bounds::new(Tuple::point(-1, -1, -1), Tuple::point(1, 1, 1)));
+            let mut objects = w.objects();
+            objects.push(Shape::plane());
+            w.set_objects(objects);
+            assert!(!w.bounds().is_finite());
+            assert!(World::new().bounds().is_empty());
+        }
+
+        {
+            let w = World::default();
+            let mut c = Camera::new(200, 100, PI / 2.0);
+            assert!(c.frame(&w.bounds(), Tuple::vector(0, 0, 1)).is_ok());
+            // The narrower vertical view is 2 * atan(0.5) wide, so the sphere
+            // of radius sqrt(3) around the unit box sits sqrt(3) / sin(atan(0.5)) away.
+            let d = 3f64.sqrt() * 5f64.sqrt();
+            let origin = c.ray_for_pixel(100, 50).origin();
+            assert!(origin == Tuple::point(0, 0, -d));
+            assert!(!w.intersect_world(c.ray_for_pixel(100, 50)).is_empty());
+            assert!(c.frame(&Shape::plane().world_bounds(), Tuple::vector(0, 0, 1)).is_err());
+            assert!(c.frame(&w.bounds(), Tuple::vector(0, 0, 0)).is_err());
+            assert!(c.frame(&w.bounds(), Tuple::vector(0, -1, 0)).is_ok());
+        }
+    }
+}
+
+mod torus {
+    use crate::{equal, roots, Matrix4x4, Num, Ray, Shape, Tuple, PI};
+
+    fn close(roots: &[Num], expected: &[Num]) -> bool {
+        roots.len() == expected.len() && roots.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-9)
+    }
+
+    #[test]
+    fn polynomial_roots() {
+        assert!(close(&roots::solve_polynomial(&[1.0, -3.0, 2.0]), &[1.0, 2.0]));
+        assert!(roots::solve_polynomial(&[1.0, 0.0, 1.0]).is_empty());
+        assert!(close(&roots::solve_polynomial(&[1.0, -6.0, 11.0, -6.0]), &[1.0, 2.0, 3.0]));
+        assert!(close(&roots::solve_polynomial(&[2.0, 0.0, 0.0, -16.0]), &[2.0]));
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        assert!(close(&roots::solve_quartic(1.0, -10.0, 35.0, -50.0, 24.0), &[1.0, 2.0, 3.0, 4.0]));
+        // x^4 - 5x^2 + 4 is biquadratic
+        assert!(close(&roots::solve_quartic(1.0, 0.0, -5.0, 0.0, 4.0), &[-2.0, -1.0, 1.0, 2.0]));
+        assert!(roots::solve_quartic(1.0, 0.0, 1.0, 0.0, 1.0).is_empty());
+        // Tiny coefficients all round still describe the same polynomial.
+        assert!(close(&roots::solve_polynomial(&[1e-14, -1e-13, 3.5e-13, -5e-13, 2.4e-13]), &[1.0, 2.0, 3.0, 4.0]));
+        assert!(close(&roots::solve_polynomial(&[1e-20, 1.0, -2.0]), &[2.0]));
+        // Roots clustered far from zero, as from a ray starting far from a
+        // small torus. Closed forms lose some of them entirely.
+        let (a, b, c, d): (Num, Num, Num, Num) = (1000.0, 1000.5, 1001.0, 1001.25);
+        let found = roots::solve_quartic(
+            1.0,
+            -(a + b + c + d),
+            a * b + a * c + a * d + b * c + b * d + c * d,
+            -(a * b * c + a * b * d + a * c * d + b * c * d),
+            a * b * c * d,
+        );
+        assert!(found.len() == 4 && found.iter().zip([a, b, c, d]).all(|(x, y)| (x - y).abs() < 1e-3));
+    }
+
+    #[test]
+    fn tori() {
+        let t = Shape::torus(1, 0.25);
+        if let Shape::Torus(item) = &t {
+            assert!(item.major_radius() == 1.0 && item.minor_radius() == 0.25);
+        } else {
+            panic!("expected a torus");
+        }
+
+        // Straight through the ring, crossing the tube twice.
+        let xs = t.intersect(Ray::new(Tuple::point(-5, 0, 0), Tuple::vector(1, 0, 0)));
+        let ts: Vec<Num> = xs.iter().map(|i| i.t()).collect();
+        assert!(ts.len() == 4);
+        for (t, expected) in ts.iter().zip([3.75, 4.25, 5.75, 6.25]) {
+            assert!(equal(*t, expected));
+        }
+
+        // Down through the tube only, and through the hole without touching it.
+        let xs = t.intersect(Ray::new(Tuple::point(1, 5, 0), Tuple::vector(0, -1, 0)));
+        assert!(xs.len() == 2 && equal(xs[0].t(), 4.75) && equal(xs[1].t(), 5.25));
+        assert!(t.intersect(Ray::new(Tuple::point(0, 5, 0), Tuple::vector(0, -1, 0))).is_empty());
+        assert!(t.intersect(Ray::new(Tuple::point(-5, 2, 0), Tuple::vector(1, 0, 0))).is_empty());
+
+        // Unnormalized directions still report t in ray units.
+        let xs = t.intersect(Ray::new(Tuple::point(1, 5, 0), Tuple::vector(0, -2, 0)));
+        assert!(equal(xs[0].t(), 2.375));
+
+        assert!(t.normal_at(Tuple::point(1.25, 0, 0)).unwrap() == Tuple::vector(1, 0, 0));
+        assert!(t.normal_at(Tuple::point(0.75, 0, 0)).unwrap() == Tuple::vector(-1, 0, 0));
+        assert!(t.normal_at(Tuple::point(0, 0.25, 1)).unwrap() == Tuple::vector(0, 1, 0));
+        let h = 0.25 / 2f64.sqrt();
+        assert!(t.normal_at(Tuple::point(0, h, -1.0 - h)).unwrap() == Tuple::vector(0, 2f64.sqrt() / 2.0, -2f64.sqrt() / 2.0));
+
+        let mut t = Shape::torus(2, 0.5);
+        t.set_transform(Matrix4x4::rotation_x(PI / 2.0)).unwrap();
+        let xs = t.intersect(Ray::new(Tuple::point(0, 2, -10), Tuple::vector(0, 0, 1)));
+        assert!(xs.len() == 2 && equal(xs[0].t(), 9.5));
+        assert!(t.world_bounds().max().get_z() - 0.5 < 1e-9);
+    }
+}
+
+mod bounded_planes {
+    use crate::{equal, Matrix4x4, Ray, Shape, Tuple, PI};
+
+    #[test]
+    fn disks() {
+        let d = Shape::disk(2);
+        let hits = [(Tuple::point(0, 1, 0), 1), (Tuple::point(1.9, 1, 0), 1), (Tuple::point(1.5, 1, 1.5), 0), (Tuple::point(0, 1, 3), 0)];
+        for (origin, count) in hits {
+            let xs = d.intersect(Ray::new(origin, Tuple::vector(0, -1, 0)));
+            assert!(xs.len() == count);
+        }
+        assert!(d.intersect(Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1))).is_empty());
+        assert!(d.normal_at(Tuple::point(1, 0, 1)).unwrap() == Tuple::vector(0, 1, 0));
+
+        let a = Shape::annulus(0.5, 1);
+        if let Shape::Disk(item) = &a {
+            assert!(item.inner_radius() == 0.5 && item.radius() == 1.0);
+        } else {
+            panic!("expected a disk");
+        }
+        assert!(a.intersect(Ray::new(Tuple::point(0.25, 1, 0), Tuple::vector(0, -1, 0))).is_empty());
+        let xs = a.intersect(Ray::new(Tuple::point(0, 1, 0.75), Tuple::vector(0, -1, 0)));
+        assert!(xs.len() == 1 && equal(xs[0].t(), 1.0));
+    }
+
+    #[test]
+    fn rectangles() {
+        let r = Shape::rectangle(4, 2);
+        assert!(r.intersect(Ray::new(Tuple::point(1.9, 1, 0.9), Tuple::vector(0, -1, 0))).len() == 1);
+        assert!(r.intersect(Ray::new(Tuple::point(1.9, 1, 1.1), Tuple::vector(0, -1, 0))).is_empty());
+        assert!(r.intersect(Ray::new(Tuple::point(2.1, 1, 0), Tuple::vector(0, -1, 0))).is_empty());
+        assert!(r.normal_at(Tuple::point(0, 0, 0)).unwrap() == Tuple::vector(0, 1, 0));
+
+        // Stood up as a picture frame facing -z.
+        let mut frame = Shape::rectangle(4, 2);
+        frame.set_transform(Matrix4x4::rotation_x(-PI / 2.0)).unwrap();
+        let xs = frame.intersect(Ray::new(Tuple::point(1, 0.5, -5), Tuple::vector(0, 0, 1)));
+        assert!(xs.len() == 1 && equal(xs[0].t(), 5.0));
+        assert!(frame.normal_at(Tuple::point(1, 0.5, 0)).unwrap() == Tuple::vector(0, 0, -1));
+        assert!(frame.world_bounds().is_finite());
+    }
+}
+
+mod signed_distance_fields {
+    use crate::{equal, Material, Matrix4x4, Num, Ray, Sdf, Shape, Tuple, World, PI};
+
+    #[test]
+    fn distances() {
+        let s = Sdf::sphere(1);
+        assert!(equal(s.distance(Tuple::point(0, 3, 0)), 2.0));
+        assert!(equal(s.distance(Tuple::point(0, 0, 0)), -1.0));
+
+        let b = Sdf::cuboid(1, 2, 3);
+        assert!(equal(b.distance(Tuple::point(4, 0, 0)), 3.0));
+        assert!(equal(b.distance(Tuple::point(2, 3, 3)), 2f64.sqrt()));
+        assert!(equal(b.distance(Tuple::point(0.5, 0, 0)), -0.5));
+        assert!(equal(Sdf::torus(2, 0.5).distance(Tuple::point(0, 0, -3)), 0.5));
+        assert!(equal(Sdf::cylinder(1, 2).distance(Tuple::point(0, 5, 0.5)), 3.0));
+
+        let pair = Sdf::sphere(1).union(Sdf::sphere(1).translated(3, 0, 0));
+        assert!(equal(pair.distance(Tuple::point(1.5, 0, 0)), 0.5));
+        let lens = Sdf::sphere(1).intersection(Sdf::sphere(1).translated(1, 0, 0));
+        assert!(equal(lens.distance(Tuple::point(0.5, 0, 0)), -0.5));
+        let bitten = Sdf::sphere(1).difference(Sdf::sphere(1).translated(1, 0, 0));
+        assert!(bitten.distance(Tuple::point(0.75, 0, 0)) > 0.0);
+        assert!(bitten.distance(Tuple::point(-0.75, 0, 0)) < 0.0);
+
+        // Blending only swells the union where both surfaces are close.
+        let blob = Sdf::sphere(1).smooth_union(Sdf::sphere(1).translated(2.2, 0, 0), 0.5);
+        assert!(blob.distance(Tuple::point(1.1, 0, 0)) < pair.distance(Tuple::point(1.1, 0, 0)).min(0.1));
+        assert!(equal(blob.distance(Tuple::point(-3, 0, 0)), 2.0));
+        assert!(blob.bounds().contains_point(Tuple::point(1.1, 0.9, 0)));
+
+        let rounded = Sdf::cuboid(1, 1, 1).rounded(0.25);
+        assert!(equal(rounded.distance(Tuple::point(2, 0, 0)), 0.75));
+        assert!(equal(rounded.bounds().max().get_x(), 1.25));
+
+        // A quarter turn per unit of y carries a slab's long side from x to z.
+        let twisted = Sdf::cuboid(2, 2, 0.25).twisted(PI / 2.0);
+        assert!(twisted.distance(Tuple::point(1.5, 0, 0)) < 0.0);
+        assert!(twisted.distance(Tuple::point(0, 1, 1.5)) < 0.0);
+        assert!(twisted.distance(Tuple::point(1.5, 1, 0)) > 0.0);
+        assert!(twisted.bounds().contains_point(Tuple::point(0, 1, 2)));
+
+        // Three copies along x only, spaced 3 apart.
+        let row = Sdf::sphere(1).repeated(Tuple::vector(3, 0, 0), [1, 0, 0]);
+        for x in [-3, 0, 3] {
+            assert!(equal(row.distance(Tuple::point(x, 0, 0)), -1.0));
+        }
+        assert!(equal(row.distance(Tuple::point(8, 0, 0)), 4.0));
+        assert!(equal(row.distance(Tuple::point(0, 5, 0)), 4.0));
+        assert!(equal(row.bounds().min().get_x(), -4.0) && equal(row.bounds().max().get_x(), 4.0));
+    }
+
+    #[test]
+    fn sphere_tracing() {
+        let ray = Ray::new(Tuple::point(0.3, 0.2, -5), Tuple::vector(0, 0, 1));
+        let mut traced = Shape::sdf(Sdf::sphere(1));
+        let mut exact = Shape::sphere();
+        for s in [&mut traced, &mut exact] {
+            s.set_transform(Matrix4x4::translation(0, 0, 1) * Matrix4x4::scaling(2, 2, 2)).unwrap();
+        }
+        let xs = traced.intersect(ray);
+        let expected = exact.intersect(ray);
+        assert!(xs.len() == 2);
+        for (a, b) in xs.iter().zip(expected.iter()) {
+            assert!((a.t() - b.t()).abs() < 1e-6);
+            let p = ray.position(a.t());
+            assert!(traced.normal_at(p).unwrap() == exact.normal_at(p).unwrap());
+        }
+
+        // Every crossing is reported, including behind the origin.
+        let row = Shape::sdf(Sdf::sphere(1).repeated(Tuple::vector(3, 0, 0), [1, 0, 0]));
+        let ts: Vec<Num> = row.intersect(Ray::new(Tuple::point(0, 0, 0), Tuple::vector(1, 0, 0))).iter().map(|i| i.t()).collect();
+        assert!(ts.len() == 6);
+        for (t, expected) in ts.iter().zip([-4.0, -2.0, -1.0, 1.0, 2.0, 4.0]) {
+            assert!((t - expected).abs() < 1e-6);
+        }
+        assert!(row.intersect(Ray::new(Tuple::point(0, 1.5, -5), Tuple::vector(0, 0, 1))).is_empty());
+        assert!(row.intersect(Ray::new(Tuple::point(0, 1.001, -5), Tuple::vector(1, 0, 0))).is_empty());
+
+        // Mixed into a world with ordinary shapes.
+        let mut world = World::default();
+        let mut blob = Shape::sdf(Sdf::cuboid(1, 1, 1).rounded(0.2));
+        blob.set_transform(Matrix4x4::translation(0, 0, 5)).unwrap();
+        blob.set_material(Material::default());
+        let mut objects = world.objects();
+        objects.push(blob.clone());
+        objects.push(Shape::plane());
+        world.set_objects(objects);
+        let xs = world.intersect_world(Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1)));
+        assert!(xs.len() == 6);
+        assert!(xs[4].object() == blob && (xs[4].t() - 8.8).abs() < 1e-6);
+        assert!(equal(xs[0].t(), 4.0));
+
+        // A ray grazing a wide slab creeps along at the smallest step for
+        // far longer than the default budget, and still finds both sides.
+        let slab = Shape::sdf(Sdf::cuboid(50, 0.5, 1));
+        let xs = slab.intersect(Ray::new(Tuple::point(-60, 0.5004, 0), Tuple::vector(1, -0.00001, 0)));
+        assert!(xs.len() == 2);
+        assert!((xs[0].t() - 40.0).abs() < 1e-3 && (xs[1].t() - 110.0).abs() < 1e-3);
+    }
+}
+
+mod heightfields {
+    use crate::{equal, sampler::Sampler, Canvas, Matrix4x4, Num, Pixel, Ray, Shape, Tuple};
+
+    #[test]
+    fn ppm_images() {
+        let plain = Canvas::from_ppm(b"P2\n# ramp\n3 2\n4\n0 1 2\n3 4 9\n").unwrap();
+        assert!(plain.width() == 3 && plain.height() == 2);
+        assert!(plain.get(1, 0) == Pixel::rgb(0.25, 0.25, 0.25));
+        assert!(plain.get(1, 1) == Pixel::white() && plain.get(2, 1) == Pixel::white());
+
+        let mut raw = b"P6 2 1 255\n".to_vec();
+        raw.extend([255, 0, 0, 0, 51, 255]);
+        let raw = Canvas::from_ppm(&raw).unwrap();
+        assert!(raw.get(0, 0) == Pixel::red() && raw.get(1, 0) == Pixel::rgb(0.0, 0.2, 1.0));
+
+        let mut wide = b"P5 1 1 65535\n".to_vec();
+        wide.extend([0x80, 0x00]);
+        assert!(Canvas::from_ppm(&wide).unwrap().get(0, 0) == Pixel::rgb(0.5, 0.5, 0.5));
+
+        assert!(Canvas::from_ppm(b"P4 1 1 1\n").is_err());
+        assert!(Canvas::from_ppm(b"P3 2 1 255\n1 2 3").is_err());
+        assert!(Canvas::from_ppm(b"P6 2 1 255\n\x01\x02").is_err());
+        assert!(Canvas::from_ppm(b"P6 65536 65536 255\n\x01\x02").is_err());
+        assert!(Canvas::from_ppm(b"P3 100000 100000 255\n1 2 3").is_err());
+    }
+
+    #[test]
+    fn terrain() {
+        assert!(Shape::heightfield(vec![vec![0.0, 1.0]]).is_err());
+        assert!(Shape::heightfield(vec![vec![0.0, 1.0], vec![0.0]]).is_err());
+
+        let flat = Shape::heightfield(vec![vec![0.5; 3]; 3]).unwrap();
+        let xs = flat.intersect(Ray::new(Tuple::point(0.3, 2, 0.7), Tuple::vector(0, -1, 0)));
+        assert!(xs.len() == 1 && equal(xs[0].t(), 1.5));
+        assert!(flat.intersect(Ray::new(Tuple::point(1.3, 2, 0.7), Tuple::vector(0, -1, 0))).is_empty());
+        assert!(flat.intersect(Ray::new(Tuple::point(-1, 0.6, 0.5), Tuple::vector(1, 0, 0))).is_empty());
+        assert!(flat.normal_at(Tuple::point(0.3, 0.5, 0.7)).unwrap() == Tuple::vector(0, 1, 0));
+
+        // A ramp rising along x, loaded from an image.
+        let mut image = Canvas::with_dimesnions(5, 2);
+        for x in 0..5 {
+            for y in 0..2 {
+                let v = x as Num / 4.0;
+                image.set(x, y, Pixel::rgb(v, v, v));
+            }
+        }
+        let ramp = Shape::heightfield_from_canvas(&image).unwrap();
+        let xs = ramp.intersect(Ray::new(Tuple::point(0.6, 5, 0.5), Tuple::vector(0, -1, 0)));
+        assert!(xs.len() == 1 && equal(xs[0].t(), 4.4));
+        let xs = ramp.intersect(Ray::new(Tuple::point(-1, 0.5, 0.5), Tuple::vector(1, 0, 0)));
+        assert!(xs.len() == 1 && equal(xs[0].t(), 1.5));
+        let s = 2f64.sqrt() / 2.0;
+        assert!(ramp.normal_at(Tuple::point(0.6, 0.6, 0.5)).unwrap() == Tuple::vector(-s, s, 0));
+
+        // Normals blend smoothly across a crease instead of jumping.
+        let ridge = Shape::heightfield(vec![vec![0.0, 1.0, 0.0], vec![0.0, 1.0, 0.0]]).unwrap();
+        assert!(ridge.normal_at(Tuple::point(0.5, 1, 0.5)).unwrap() == Tuple::vector(0, 1, 0));
+        let left = ridge.normal_at(Tuple::point(0.25, 0.5, 0.5)).unwrap();
+        assert!(left.get_x() < 0.0 && left.get_x() > -s);
+
+        // Grid traversal finds the same crossings as testing every triangle.
+        let mut rng = Sampler::new(11);
+        let heights: Vec<Vec<Num>> = (0..5).map(|_| (0..5).map(|_| rng.next()).collect()).collect();
+        let mut triangles = vec![];
+        let at = |x: usize, z: usize| Tuple::point(x as Num / 4.0, heights[z][x], z as Num / 4.0);
+        for z in 0..4 {
+            for x in 0..4 {
+                triangles.push(Shape::triangle(at(x, z), at(x + 1, z), at(x + 1, z + 1)));
+                triangles.push(Shape::triangle(at(x, z), at(x + 1, z + 1), at(x, z + 1)));
+            }
+        }
+        let mut terrain = Shape::heightfield(heights.clone()).unwrap();
+        let transform = Matrix4x4::translation(1, 0, -2) * Matrix4x4::scaling(10, 2, 10);
+        terrain.set_transform(transform).unwrap();
+        let mut mesh = Shape::group_of(triangles);
+        mesh.set_transform(transform).unwrap();
+        for _ in 0..200 {
+            let origin = Tuple::point(rng.next() * 14.0 - 2.0, rng.next() * 3.0, rng.next() * 14.0 - 14.0);
+            let target = Tuple::point(1.0 + rng.next() * 10.0, rng.next() * 2.0, rng.next() * 10.0 - 2.0);
+            let ray = Ray::new(origin, target - origin);
+            let (a, b) = (terrain.intersect(ray), mesh.intersect(ray));
+            assert!(a.len() == b.len());
+            assert!(a.iter().zip(b.iter()).all(|(x, y)| (x.t() - y.t()).abs() < 1e-9));
+        }
+    }
+}
+
+mod metaballs {
+    use crate::{equal, Falloff, Metaball, Ray, Shape, Tuple};
+
+    #[test]
+    fn blobs() {
+        let ball = |x: f64, radius: f64, weight: f64| Metaball::new(Tuple::point(x, 0, 0), radius, weight);
+        assert!(Shape::metaballs(vec![ball(0.0, 1.0, 1.0)], 0, Falloff::Quartic).is_err());
+        assert!(Shape::metaballs(vec![ball(0.0, 0.0, 1.0)], 0.5, Falloff::Quartic).is_err());
+
+        // (1 - r² / 4)² = 1/4 at r = √2.
+        let single = Shape::metaballs(vec![ball(0.0, 2.0, 1.0)], 0.25, Falloff::Quartic).unwrap();
+        let r = 2f64.sqrt();
+        let xs = single.intersect(Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1)));
+        assert!(xs.len() == 2 && equal(xs[0].t(), 5.0 - r) && equal(xs[1].t(), 5.0 + r));
+        let xs = single.intersect(Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 4)));
+        assert!(equal(xs[0].t(), (5.0 - r) / 4.0));
+        assert!(single.normal_at(Tuple::point(0, 0, -r)).unwrap() == Tuple::vector(0, 0, -1));
+        assert!(single.bounds().max() == Tuple::point(2, 2, 2));
+
+        // Close balls merge into one blob, far ones stay apart.
+        let near = Shape::metaballs(vec![ball(-1.0, 2.0, 1.0), ball(1.0, 2.0, 1.0)], 0.25, Falloff::Quartic).unwrap();
+        assert!(near.intersect(Ray::new(Tuple::point(-5, 0, 0), Tuple::vector(1, 0, 0))).len() == 2);
+        assert!(near.intersect(Ray::new(Tuple::point(0, -5, 0), Tuple::vector(0, 1, 0))).len() == 2);
+        let far = Shape::metaballs(vec![ball(-3.0, 2.0, 1.0), ball(3.0, 2.0, 1.0)], 0.25, Falloff::Quartic).unwrap();
+        assert!(far.intersect(Ray::new(Tuple::point(-6, 0, 0), Tuple::vector(1, 0, 0))).len() == 4);
+        assert!(far.intersect(Ray::new(Tuple::point(0, -5, 0), Tuple::vector(0, 1, 0))).is_empty());
+
+        // A negative ball bites a hole through the middle.
+        let bitten = Shape::metaballs(vec![ball(0.0, 2.0, 1.0), ball(0.0, 1.0, -1.0)], 0.25, Falloff::Quartic).unwrap();
+        assert!(bitten.intersect(Ray::new(Tuple::point(-5, 0, 0), Tuple::vector(1, 0, 0))).len() == 4);
+        assert!(bitten.bounds().max() == Tuple::point(2, 2, 2));
+
+        // Every crossing lies on the threshold, with the normal against the field's slope.
+        let balls = vec![ball(-0.8, 1.5, 1.0), ball(0.9, 1.2, 0.7), Metaball::new(Tuple::point(0, 0.8, 0.3), 1.0, 0.5)];
+        let blob = Shape::metaballs(balls, 0.3, Falloff::Wyvill).unwrap();
+        let Shape::Metaballs(item) = &blob else { panic!("expected metaballs") };
+        assert!(item.falloff() == Falloff::Wyvill && item.balls().len() == 3);
+        let ray = Ray::new(Tuple::point(-4, 0.2, -3), Tuple::vector(4, 0.1, 3));
+        let xs = blob.intersect(ray);
+        assert!(!xs.is_empty());
+        for i in xs {
+            let p = ray.position(i.t());
+            assert!((item.field_at(p) - 0.3).abs() < 1e-9);
+            let n = blob.normal_at(p).unwrap();
+            assert!(item.field_at(p + n * 0.001) < 0.3 && item.field_at(p - n * 0.001) > 0.3);
+        }
+    }
+}
+
+mod quadrics {
+    use crate::{equal, Bounds, Matrix4x4, Quadric, Ray, Shape, Tuple};
+
+    #[test]
+    fn quadric_surfaces() {
+        // The unit sphere written as a quadric.
+        let q = Shape::quadric(Matrix4x4::new([
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, -1.0,
+        ]));
+        let s = Shape::sphere();
+        for ray in [
+            Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1)),
+            Ray::new(Tuple::point(0.5, 0.2, 0), Tuple::vector(0.3, -1, 0.2)),
+            Ray::new(Tuple::point(0, 2, -5), Tuple::vector(0, 0, 1)),
+        ] {
+            let (a, b) = (q.intersect(ray), s.intersect(ray));
+            assert!(a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| equal(x.t(), y.t())));
+            for i in a {
+                let p = ray.position(i.t());
+                assert!(q.normal_at(p).unwrap() == s.normal_at(p).unwrap());
+            }
+        }
+        assert!(q.bounds() == s.bounds());
+
+        // An ellipsoid without non-uniform scaling, matching a scaled sphere.
+        let e = Shape::ellipsoid(2, 1, 1);
+        let mut scaled = Shape::sphere();
+        scaled.set_transform(Matrix4x4::scaling(2, 1, 1)).unwrap();
+        let ray = Ray::new(Tuple::point(-5, 0.3, 0.2), Tuple::vector(1, 0.1, 0));
+        let (a, b) = (e.intersect(ray), scaled.intersect(ray));
+        assert!(a.len() == 2 && equal(a[0].t(), b[0].t()) && equal(a[1].t(), b[1].t()));
+        let p = ray.position(a[0].t());
+        assert!(e.normal_at(p).unwrap() == scaled.normal_at(p).unwrap());
+        assert!(e.bounds() == Bounds::new(Tuple::point(-2, -1, -1), Tuple::point(2, 1, 1)));
+        // Large ellipsoids have a tiny determinant but still finite bounds.
+        for r in [10.0, 1000.0] {
+            let big = Shape::ellipsoid(r, r, r);
+            assert!(big.bounds().is_finite());
+            assert!(big.bounds() == Bounds::new(Tuple::point(-r, -r, -r), Tuple::point(r, r, r)));
+        }
+
+        // Off-centre coefficients still give tight bounds.
+        let moved = Matrix4x4::translation(1, 2, 3).inverse().unwrap();
+        let sphere = Matrix4x4::new([
+            0.25, 0.0, 0.0, 0.0,
+            0.0, 0.25, 0.0, 0.0,
+            0.0, 0.0, 0.25, 0.0,
+            0.0, 0.0, 0.0, -1.0,
+        ]);
+        let off = Shape::quadric(moved.transpose() * sphere * moved);
+        assert!(off.bounds() == Bounds::new(Tuple::point(-1, 0, 1), Tuple::point(3, 4, 5)));
+        let imaginary = Shape::quadric(Matrix4x4::identity());
+        assert!(imaginary.bounds().is_empty());
+        assert!(imaginary.intersect(Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1))).is_empty());
+
+        // Paraboloids, only the symmetric part of the coefficients counting.
+        let mut lopsided = Quadric::paraboloid().coefficients();
+        lopsided.set(1, 3, -1);
+        lopsided.set(3, 1, 0);
+        assert!(Shape::quadric(lopsided) == Shape::Quadric(Quadric::paraboloid()));
+        let bowl = Shape::Quadric(Quadric::paraboloid());
+        let xs = bowl.intersect(Ray::new(Tuple::point(1, 5, 0), Tuple::vector(0, -1, 0)));
+        assert!(xs.len() == 1 && equal(xs[0].t(), 4.0));
+        assert!(bowl.normal_at(Tuple::point(0, 0, 0)).unwrap() == Tuple::vector(0, -1, 0));
+        assert!(!bowl.bounds().is_finite());
+        let clip = Bounds::new(Tuple::point(-3, -1, -3), Tuple::point(3, 4, 3));
+        let cup = Shape::Quadric(Quadric::paraboloid().clipped(clip));
+        assert!(cup.bounds() == clip);
+        assert!(cup.intersect(Ray::new(Tuple::point(-5, 5, 0), Tuple::vector(1, 0, 0))).is_empty());
+        let xs = cup.intersect(Ray::new(Tuple::point(-5, 1, 0), Tuple::vector(1, 0, 0)));
+        assert!(xs.len() == 2 && equal(xs[0].t(), 4.0) && equal(xs[1].t(), 6.0));
+
+        // Hyperboloids of one and two sheets.
+        let one = Shape::Quadric(Quadric::hyperboloid(true));
+        let xs = one.intersect(Ray::new(Tuple::point(-5, 3f64.sqrt(), 0), Tuple::vector(1, 0, 0)));
+        assert!(xs.len() == 2 && equal(xs[0].t(), 3.0) && equal(xs[1].t(), 7.0));
+        assert!(one.intersect(Ray::new(Tuple::point(0, -5, 0), Tuple::vector(0, 1, 0))).is_empty());
+        let two = Shape::Quadric(Quadric::hyperboloid(false));
+        let xs = two.intersect(Ray::new(Tuple::point(0, -5, 0), Tuple::vector(0, 1, 0)));
+        assert!(xs.len() == 2 && equal(xs[0].t(), 4.0) && equal(xs[1].t(), 6.0));
+        assert!(two.intersect(Ray::new(Tuple::point(-5, 0, 0), Tuple::vector(1, 0, 0))).is_empty());
+        assert!(two.normal_at(Tuple::point(0, 1, 0)).unwrap() == Tuple::vector(0, -1, 0));
+    }
+}
+
+mod custom_shapes {
+    use crate::{Bounds, Matrix4x4, Num, Ray, Shape, ShapeGeometry, Tuple, World, PI};
+
+    // A unit sphere as a downstream crate would write it.
+    struct Ball;
+
+    impl ShapeGeometry for Ball {
+        fn local_intersect(&self, ray: Ray) -> Vec<Num> {
+            let o = ray.origin() - Tuple::point(0, 0, 0);
+            let d = ray.direction();
+            let (a, b, c) = (d.dot(&d), 2.0 * d.dot(&o), o.dot(&o) - 1.0);
+            let discriminant = b * b - 4.0 * a * c;
+            if discriminant < 0.0 {
+                return vec![];
+            }
+            let root = discriminant.sqrt();
+            vec![(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)]
+        }
+        fn local_normal_at(&self, point: Tuple) -> Tuple {
+            point - Tuple::point(0, 0, 0)
+        }
+        fn bounds(&self) -> Bounds {
+            Bounds::new(Tuple::point(-1, -1, -1), Tuple::point(1, 1, 1))
+        }
+        fn uv_at(&self, point: Tuple) -> Option<(Num, Num)> {
+            let u = 0.5 + point.get_z().atan2(point.get_x()) / (2.0 * PI);
+            Some((u, 0.5 + point.get_y().asin() / PI))
+        }
+    }
+
+    // Only says where rays cross y = 0.
+    struct Floor;
+
+    impl ShapeGeometry for Floor {
+        fn local_intersect(&self, ray: Ray) -> Vec<Num> {
+            let dy = ray.direction().get_y();
+            if dy == 0.0 {
+                vec![]
+            } else {
+                vec![-ray.origin().get_y() / dy]
+            }
+        }
+        fn local_normal_at(&self, _point: Tuple) -> Tuple {
+            Tuple::vector(0, 1, 0)
+        }
+    }
+
+    #[test]
+    fn user_defined_geometry() {
+        let mut ball = Shape::custom(Ball);
+        ball.set_transform(Matrix4x4::translation(0, 1, 0)).unwrap();
+        let xs = ball.intersect(Ray::new(Tuple::point(0, 1, -5), Tuple::vector(0, 0, 1)));
+        assert!(xs.len() == 2 && xs[0].t() == 4.0 && xs[1].t() == 6.0);
+        assert!((xs[0].u() - 0.25).abs() < 1e-9 && (xs[0].v() - 0.5).abs() < 1e-9);
+        assert!(ball.normal_at(Tuple::point(0, 2, 0)).unwrap() == Tuple::vector(0, 1, 0));
+        assert!(ball.world_bounds().max() == Tuple::point(1, 2, 1));
+        assert!(ball.clone() == ball && Shape::custom(Ball) != Shape::custom(Ball));
+
+        let floor = Shape::custom(Floor);
+        assert!(!floor.bounds().is_finite());
+        let xs = floor.intersect(Ray::new(Tuple::point(0, 2, 0), Tuple::vector(0, -1, 0)));
+        assert!(xs.len() == 1 && xs[0].u() == 0.0);
+
+        // Shades exactly like the built-in sphere it copies.
+        let builtin = World::default();
+        let mut world = World::default();
+        let mut objects = world.objects();
+        let mut replacement = Shape::custom(Ball);
+        replacement.set_material(objects[0].material());
+        objects[0] = replacement;
+        world.set_objects(objects);
+        for ray in [
+            Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1)),
+            Ray::new(Tuple::point(0.4, 0.3, -5), Tuple::vector(0, 0, 1)),
+            Ray::new(Tuple::point(0, 0, 0.75), Tuple::vector(0, 0, -1)),
+        ] {
+            assert!(world.color_at(ray, 5) == builtin.color_at(ray, 5));
+        }
+    }
+}
+
+mod instancing {
+    use std::sync::Arc;
+
+    use crate::{intersection, Material, Matrix4x4, Pixel, Ray, Shape, Tuple, World};
+
+    fn pyramid() -> Shape {
+        let apex = Tuple::point(0, 1, 0);
+        let base = [Tuple::point(-1, 0, -1), Tuple::point(1, 0, -1), Tuple::point(1, 0, 1), Tuple::point(-1, 0, 1)];
+        let mut g = Shape::group();
+        for i in 0..4 {
+            g.add_child(Shape::triangle(base[i], base[(i + 1) % 4], apex)).unwrap();
+        }
+        g
+    }
+
+    #[test]
+    fn shared_instances() {
+        let mut shared = pyramid();
+        shared.set_transform(Matrix4x4::scaling(1, 2, 1)).unwrap();
+        let shared = Arc::new(shared);
+
+        let instances: Vec<Shape> = (0..500)
+            .map(|i| {
+                let mut s = Shape::instance(shared.clone());
+                s.set_transform(Matrix4x4::translation(3 * (i % 25), 0, 3 * (i / 25))).unwrap();
+                s
+            })
+            .collect();
+        assert!(Arc::strong_count(&shared) == 501);
+        let Shape::Instance(first) = &instances[0] else { panic!("expected an instance") };
+        assert!(Arc::ptr_eq(first.target(), &shared) && first.material_override().is_none());
+
+        // Same hits as moving a copy of the geometry there.
+        let mut copy = (*shared).clone();
+        copy.set_transform(Matrix4x4::translation(6, 0, 3) * Matrix4x4::scaling(1, 2, 1)).unwrap();
+        let ray = Ray::new(Tuple::point(6.2, 0.5, -5), Tuple::vector(0, 0, 1));
+        let (a, b) = (instances[27].intersect(ray), copy.intersect(ray));
+        assert!(a.len() == 2 && a.len() == b.len());
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert!(x.t() == y.t());
+            let p = ray.position(x.t());
+            assert!(x.object().normal_at(p).unwrap() == y.object().normal_at(p).unwrap());
+        }
+        assert!(instances[27].world_bounds() == copy.world_bounds());
+        assert!(instances[27].normal_at(ray.position(a[0].t())).is_none());
+        assert!(instances[0] != instances[1] && instances[0] == instances[0].clone());
+
+        // The override restyles only this instance.
+        let mut red = Material::default();
+        red.set_color(Pixel::red());
+        let mut styled = instances[27].clone();
+        styled.set_material(red);
+        assert!(styled.material() == red);
+        assert!(styled.intersect(ray).iter().all(|i| i.object().material() == red));
+        assert!(instances[27].intersect(ray).iter().all(|i| i.object().material() == Material::default()));
+        assert!(shared.children().iter().all(|c| c.material() == Material::default()));
+
+        let mut world = World::default();
+        world.set_objects(instances);
+        let xs = world.intersect_world(Ray::new(Tuple::point(12, 10, 30), Tuple::vector(0, -1, 0)));
+        let hit = intersection::hit(xs).unwrap();
+        assert!((hit.t() - 8.0).abs() < 1e-9);
+    }
+}
+
+mod object_ids {
+    use crate::{Camera, Material, Matrix4x4, Ray, Shape, Tuple, World, PI};
+
+    fn glass_sphere() -> Shape {
+        let mut s = Shape::sphere();
+        let mut m = Material::default();
+        m.set_transparency(1.0);
+        m.set_refractive_index(1.5);
+        s.set_material(m);
+        s
+    }
+
+    #[test]
+    fn world_object_ids() {
+        let mut w = World::new();
+        let a = w.add_named_object("left", glass_sphere());
+        let b = w.add_object(glass_sphere());
+        assert!(a != b && w.object_ids() == vec![a, b]);
+        assert!(w.find_object("left") == Some(a) && w.object_name(a) == Some("left"));
+        assert!(w.object_name(b).is_none() && w.find_object("right").is_none());
+        assert!(w.object(b) == Some(&glass_sphere()));
+
+        // Two identical glass spheres in the same place: each crossing is
+        // still matched to the sphere it belongs to.
+        let ray = Ray::new(Tuple::point(0, 0, -5), Tuple::vector(0, 0, 1));
+        let xs = w.intersect_world(ray);
+        assert!(xs.len() == 4);
+        let owners: Vec<_> = xs.iter().map(|i| i.object_id().unwrap()).collect();
+        assert!(owners.contains(&a) && owners.contains(&b));
+        let expected = [(1.0, 1.5), (1.5, 1.5), (1.5, 1.5), (1.5, 1.0)];
+        for (i, (n1, n2)) in expected.iter().enumerate() {
+            let comps = xs[i].prepare_computations(ray, xs.clone());
+            assert!(comps.n1() == *n1 && comps.n2() == *n2);
+            assert!(comps.object_id() == xs[i].object_id());
+        }
+        assert!(glass_sphere().intersect(ray).iter().all(|i| i.object_id().is_none()));
+
+        // Replacing or removing one object leaves the other ids alone.
+        w.set_object(b, Shape::cube()).unwrap();
+        assert!(w.object_ids() == vec![a, b] && w.object(b) == Some(&Shape::cube()));
+        assert!(w.object_name(a) == Some("left"));
+        assert!(w.remove_object(a) == Some(glass_sphere()));
+        assert!(w.object_ids() == vec![b] && w.object(a).is_none() && w.find_object("left").is_none());
+        assert!(w.pick(ray) == Some(b));
+        assert!(w.remove_object(a).is_none() && w.set_object(a, Shape::sphere()).is_err());
+
+        // Hits inside a group carry the group's id; replaced objects get new ids.
+        let mut g = Shape::group();
+        g.add_child(Shape::sphere()).unwrap();
+        g.set_transform(Matrix4x4::translation(0, 0, 10)).unwrap();
+        w.set_objects(vec![g]);
+        let group_id = w.object_ids()[0];
+        assert!(group_id != a && group_id != b && w.object(a).is_none());
+        assert!(w.pick(ray) == Some(group_id));
+        assert!(w.pick(Ray::new(Tuple::point(0, 5, -5), Tuple::vector(0, 0, 1))).is_none());
+
+        let w = World::default();
+        let c = Camera::with_transform(
+            11,
+            11,
+            PI / 2.0,
+            Matrix4x4::view(Tuple::point(0, 0, -5), Tuple::point(0, 0, 0), Tuple::vector(0, 1, 0)),
+        ).unwrap();
+        let outer = w.object_ids()[0];
+        assert!(c.pick(&w, 5, 5) == Some(outer));
+        let ids = c.render_object_ids(&w);
+        assert!(ids.len() == 11 && ids[0].len() == 11);
+        assert!(ids[5][5] == Some(outer) && ids[0][0].is_none());
+    }
+}
+
+mod cached_transforms {
+    use crate::{Camera, Matrix4x4, Pattern, Pixel, Ray, Shape, Tuple, PI};
+
+    #[test]
+    fn singular_transforms_are_rejected() {
+        let flat = Matrix4x4::scaling(1, 0, 1);
+        let mut s = Shape::sphere();
+        s.set_transform(Matrix4x4::translation(0, 1, 0)).unwrap();
+        assert!(s.set_transform(flat).is_err());
+        assert!(s.transform() == Matrix4x4::translation(0, 1, 0));
+
+        let mut p = Pattern::stripe(Pixel::white(), Pixel::black());
+        assert!(p.set_transform(flat).is_err() && p.transform() == Matrix4x4::identity());
+        p.set_transform(Matrix4x4::scaling(2, 2, 2)).unwrap();
+        assert!(p.at_object(&s, Tuple::point(1.5, 1, 0)) == Pixel::white());
+        assert!(p.at_object(&s, Tuple::point(2.5, 1, 0)) == Pixel::black());
+
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        assert!(c.set_transform(flat).is_err() && c.transform() == Matrix4x4::identity());
+        assert!(Camera::with_transform(11, 11, PI / 2.0, flat).is_err());
+
+        // Only singular matrices are rejected, however small the scale.
+        for k in [0.04, 0.001, 1e-6] {
+            let mut tiny = Shape::sphere();
+            tiny.set_transform(Matrix4x4::scaling(k, k, k)).unwrap();
+            let xs = tiny.intersect(Ray::new(Tuple::point(0, 0, -1), Tuple::vector(0, 0, 1)));
+            assert!(xs.len() == 2 && (xs[0].t() - (1.0 - k)).abs() < 1e-9);
+        }
+        // The third row is the sum of the first two.
+        let rank3 = Matrix4x4::new([1, 2, 0, 0, 0, 1, 3, 0, 1, 3, 3, 0, 0, 0, 0, 1]);
+        assert!(!rank3.invertible() && Shape::sphere().set_transform(rank3).is_err());
+
+        // Transforms composed through groups keep a matching inverse.
+        let mut inner = Shape::group();
+        inner.set_transform(Matrix4x4::rotation_y(PI / 3.0) * Matrix4x4::scaling(1, 2, 3)).unwrap();
+        let mut leaf = Shape::sphere();
+        leaf.set_transform(Matrix4x4::translation(0.5, 0, 0)).unwrap();
+        inner.add_child(leaf).unwrap();
+        let mut outer = Shape::group();
+        outer.set_transform(Matrix4x4::translation(0, 0, 5) * Matrix4x4::rotation_x(0.3)).unwrap();
+        outer.add_child(inner).unwrap();
+        let xs = outer.intersect(Ray::new(Tuple::point(0.2, 0.1, -10), Tuple::vector(0, 0, 1)));
+        assert!(!xs.is_empty());
+        let hit = xs[0].object();
+        let p = Tuple::point(1, 2, 3);
+        assert!(hit.world_to_object(p) == hit.transform().inverse().unwrap() * p);
+        let n = Tuple::vector(0.3, 0.4, 0.5);
+        let mut expected = hit.transform().inverse().unwrap().transpose() * n;
+        expected.set_w(0);
+        assert!(hit.normal_to_world(n) == expected.normalize());
+    }
+}
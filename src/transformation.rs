@@ -1,3 +1,5 @@
+use std::ops::Mul;
+
 use crate::{matrix4x4::Matrix4x4, tuple::Tuple, Num};
 
 impl Matrix4x4 {
@@ -133,6 +135,12 @@ pub struct TransformationBuilder {
     mats: Vec<Matrix4x4>,
 }
 
+impl Default for TransformationBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TransformationBuilder {
     pub fn new() -> TransformationBuilder {
         Self { mats: Vec::new() }
@@ -281,11 +289,67 @@ impl TransformationBuilder {
             return m;
         }
 
-        while !self.mats.is_empty() {
-            let new = self.mats.pop().unwrap();
+        while let Some(new) = self.mats.pop() {
             m = m * new;
         }
 
         m
     }
 }
+
+// A transform along with its inverse and inverse transpose, worked out once
+// when it is set instead of for every ray and normal.
+#[derive(Clone, Copy)]
+pub(crate) struct Transform {
+    matrix: Matrix4x4,
+    inverse: Matrix4x4,
+    inverse_transpose: Matrix4x4,
+}
+
+impl Transform {
+    pub(crate) fn new(matrix: Matrix4x4) -> Result<Transform, String> {
+        let inverse = matrix.inverse().map_err(|_| "transform is not invertible".to_string())?;
+        Ok(Transform {
+            matrix,
+            inverse,
+            inverse_transpose: inverse.transpose(),
+        })
+    }
+    pub(crate) fn identity() -> Transform {
+        Transform {
+            matrix: Matrix4x4::identity(),
+            inverse: Matrix4x4::identity(),
+            inverse_transpose: Matrix4x4::identity(),
+        }
+    }
+
+    pub(crate) fn matrix(&self) -> Matrix4x4 {
+        self.matrix
+    }
+    pub(crate) fn inverse(&self) -> Matrix4x4 {
+        self.inverse
+    }
+    pub(crate) fn inverse_transpose(&self) -> Matrix4x4 {
+        self.inverse_transpose
+    }
+}
+
+// Composes without inverting again: (AB)⁻¹ = B⁻¹A⁻¹.
+impl Mul<Transform> for Transform {
+    type Output = Transform;
+
+    fn mul(self, rhs: Transform) -> Self::Output {
+        let inverse = rhs.inverse * self.inverse;
+        Transform {
+            matrix: self.matrix * rhs.matrix,
+            inverse,
+            inverse_transpose: inverse.transpose(),
+        }
+    }
+}
+
+impl PartialEq<Transform> for Transform {
+    fn eq(&self, other: &Transform) -> bool {
+        self.matrix == other.matrix
+    }
+}
@@ -1,28 +1,62 @@
+use std::sync::OnceLock;
+
 use crate::{
+    bvh::Bvh,
     hit,
     img::Pixel,
     intersection::{self, Computations, Intersection},
     light::Light,
     material::Material,
     matrix4x4::Matrix4x4,
-    ray::Ray,
-    shape::Shape,
-    tuple::Tuple, equal,
+    occlusion::AmbientOcclusion,
+    photon_map::PhotonMap,
+    ray::{Ray, RayKind},
+    shape::{Bounds, Shape},
+    tuple::Tuple, equal, PI,
 };
 
+// Identifies an object added to a `World`. Ids are never reused, even after
+// the objects are replaced, and every hit carries the id of the object hit.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct ObjectId(u32);
+
+impl ObjectId {
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+}
+
 pub struct World {
     objects: Vec<Shape>,
+    // Parallel to `objects`, ids in increasing order.
+    ids: Vec<ObjectId>,
+    names: Vec<Option<String>>,
+    next_id: u32,
     light: Option<Light>,
+    transparent_shadows: bool,
+    ambient_occlusion: Option<AmbientOcclusion>,
+    caustics: Option<PhotonMap>,
+    bvh: OnceLock<Bvh>,
 }
 
 impl World {
     pub fn new() -> World {
         World {
             objects: vec![],
+            ids: vec![],
+            names: vec![],
+            next_id: 0,
             light: None,
+            transparent_shadows: false,
+            ambient_occlusion: None,
+            caustics: None,
+            bvh: OnceLock::new(),
         }
     }
-    pub fn default() -> World {
+}
+
+impl Default for World {
+    fn default() -> World {
         let light = Light::point(Tuple::point(-10, 10, -10), Pixel::white());
         let mut s1 = Shape::sphere();
         let mut s1_m = Material::default();
@@ -31,11 +65,18 @@ impl World {
         s1_m.set_specular(0.2);
         s1.set_material(s1_m);
         let mut s2 = Shape::sphere();
-        s2.set_transform(Matrix4x4::scaling(0.5, 0.5, 0.5));
+        s2.set_transform(Matrix4x4::scaling(0.5, 0.5, 0.5)).unwrap();
 
         World {
             objects: vec![s1, s2],
+            ids: vec![ObjectId(0), ObjectId(1)],
+            names: vec![None, None],
+            next_id: 2,
             light: Some(light),
+            transparent_shadows: false,
+            ambient_occlusion: None,
+            caustics: None,
+            bvh: OnceLock::new(),
         }
     }
 }
@@ -50,34 +91,134 @@ impl World {
     pub fn set_light(&mut self, light: Light) {
         self.light = Some(light);
     }
+    // Replaces every object, giving each a new id.
     pub fn set_objects(&mut self, objs: Vec<Shape>) {
-        self.objects = objs;
+        self.objects = vec![];
+        self.ids = vec![];
+        self.names = vec![];
+        for obj in objs {
+            self.add_object(obj);
+        }
+    }
+    pub fn add_object(&mut self, obj: Shape) -> ObjectId {
+        let id = ObjectId(self.next_id);
+        self.next_id += 1;
+        self.objects.push(obj);
+        self.ids.push(id);
+        self.names.push(None);
+        self.bvh = OnceLock::new();
+        id
+    }
+    pub fn add_named_object<S>(&mut self, name: S, obj: Shape) -> ObjectId
+    where
+        S: Into<String>,
+    {
+        let id = self.add_object(obj);
+        *self.names.last_mut().unwrap() = Some(name.into());
+        id
+    }
+    // Replaces one object in place; it keeps its id and name, and every
+    // other object keeps its own.
+    pub fn set_object(&mut self, id: ObjectId, obj: Shape) -> Result<(), String> {
+        let index = self.ids.binary_search(&id).map_err(|_| format!("no object with id {}", id.value()))?;
+        self.objects[index] = obj;
+        self.bvh = OnceLock::new();
+        Ok(())
+    }
+    // Ids are never reused, so the removed one stays unknown afterwards.
+    pub fn remove_object(&mut self, id: ObjectId) -> Option<Shape> {
+        let index = self.ids.binary_search(&id).ok()?;
+        self.ids.remove(index);
+        self.names.remove(index);
+        self.bvh = OnceLock::new();
+        Some(self.objects.remove(index))
+    }
+    // Ids of `objects()`, in the same order.
+    pub fn object_ids(&self) -> Vec<ObjectId> {
+        self.ids.clone()
+    }
+    pub fn object(&self, id: ObjectId) -> Option<&Shape> {
+        self.ids.binary_search(&id).ok().map(|i| &self.objects[i])
+    }
+    pub fn object_name(&self, id: ObjectId) -> Option<&str> {
+        self.ids.binary_search(&id).ok().and_then(|i| self.names[i].as_deref())
+    }
+    pub fn find_object(&self, name: &str) -> Option<ObjectId> {
+        self.names.iter().position(|n| n.as_deref() == Some(name)).map(|i| self.ids[i])
+    }
+    pub fn transparent_shadows(&self) -> bool {
+        self.transparent_shadows
+    }
+    pub fn set_transparent_shadows(&mut self, val: bool) {
+        self.transparent_shadows = val;
+    }
+    pub fn ambient_occlusion(&self) -> Option<AmbientOcclusion> {
+        self.ambient_occlusion
+    }
+    pub fn set_ambient_occlusion(&mut self, ao: Option<AmbientOcclusion>) {
+        self.ambient_occlusion = ao;
+    }
+    pub fn caustics(&self) -> Option<&PhotonMap> {
+        self.caustics.as_ref()
+    }
+    pub fn set_caustics(&mut self, caustics: Option<PhotonMap>) {
+        self.caustics = caustics;
+    }
+
+    fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| Bvh::build(&self.objects))
+    }
+
+    // World space bounds of every object, unbounded if any object is.
+    pub fn bounds(&self) -> Bounds {
+        self.objects.iter().fold(Bounds::empty(), |b, obj| b.merge(&obj.world_bounds()))
     }
 
     pub fn intersect_world(&self, ray: Ray) -> Vec<Intersection> {
-        let mut hits = Vec::<Intersection>::new();
-        for obj in self.objects().clone().iter_mut() {
-            hits.append(&mut obj.intersect(ray).clone());
-        }
+        let mut hits = vec![];
+        self.bvh().visit(ray, |i| {
+            hits.extend(self.objects[i].intersect(ray).into_iter().map(|x| x.with_object_id(self.ids[i])));
+        });
 
         hits.sort_by(|a, b| a.t().partial_cmp(&b.t()).unwrap());
         hits
     }
 
+    pub fn intersect_world_for(&self, ray: Ray, kind: RayKind) -> Vec<Intersection> {
+        let mut hits = self.intersect_world(ray);
+        hits.retain(|i| i.object().flags().visible_to(kind));
+        hits
+    }
+
+    // Object seen first along the ray, as the camera sees it.
+    pub fn pick(&self, ray: Ray) -> Option<ObjectId> {
+        hit(self.intersect_world_for(ray, RayKind::Camera)).and_then(|h| h.object_id())
+    }
+
     pub fn shade_hit(&self, comps: Computations, remaining: i32) -> Pixel {
-        let shadowed = self.is_shadowed(comps.over_point());
+        let transmission = if comps.object().flags().receives_shadow() {
+            self.is_shadowed(comps.over_point())
+        } else {
+            Pixel::white()
+        };
+
+        let occlusion = match self.ambient_occlusion {
+            Some(ao) => self.ambient_occlusion_at(comps.over_point(), comps.normalv(), ao),
+            None => 1.0,
+        };
 
-        let surface = comps.object().material().lighting(
+        let surface = comps.object().material().lighting_occluded(
             &comps.object(),
             &self.light().unwrap(),
             comps.over_point(), // CHanged from point
             comps.eyev(),
             comps.normalv(),
-            shadowed,
+            transmission,
+            occlusion,
         );
 
-        let mut reflected = self.reflected_color(comps, remaining);
-        let mut refracted = self.refracted_color(comps, remaining);
+        let mut reflected = self.reflected_color(comps.clone(), remaining);
+        let mut refracted = self.refracted_color(comps.clone(), remaining);
 
         let material = comps.object().material();
         if material.reflective() > 0.0 && material.transparency() > 0.0 {
@@ -86,11 +227,27 @@ impl World {
             refracted = refracted * (1.0 - reflectance);
         }
 
-        surface + reflected + refracted
+        surface + reflected + refracted + self.caustic_color(comps)
+    }
+
+    pub fn caustic_color(&self, comps: Computations) -> Pixel {
+        let map = match &self.caustics {
+            Some(map) => map,
+            None => return Pixel::black(),
+        };
+        let object = comps.object();
+        let material = object.material();
+        let irradiance = map.irradiance(comps.point(), comps.normalv());
+
+        material.color_at(&object, comps.over_point()) * irradiance * (material.diffuse() / PI)
     }
 
     pub fn color_at(&self, ray: Ray, remaining: i32) -> Pixel {
-        let xs = self.intersect_world(ray);
+        self.color_at_for(ray, RayKind::Camera, remaining)
+    }
+
+    pub fn color_at_for(&self, ray: Ray, kind: RayKind, remaining: i32) -> Pixel {
+        let xs = self.intersect_world_for(ray, kind);
         if let Some(hit) = intersection::hit(xs.clone()) {
             let comps = hit.prepare_computations(ray, xs);
             self.shade_hit(comps, remaining)
@@ -99,20 +256,51 @@ impl World {
         }
     }
 
-    pub fn is_shadowed(&self, point: Tuple) -> bool {
-        let v = self.light.unwrap().position() - point;
+    // Light reaching `point` from the light source: white when nothing is in
+    // the way, black when something opaque is, and tinted by each transparent
+    // object in between when transparent shadows are on.
+    pub fn is_shadowed(&self, point: Tuple) -> Pixel {
+        self.transmission_between(point, self.light.unwrap().position())
+    }
+
+    pub fn transmission_between(&self, point: Tuple, target: Tuple) -> Pixel {
+        let v = target - point;
         let distance = v.magnitude();
-        let direction = v.normalize();
+        let r = Ray::new(point, v.normalize());
+        let xs = self.intersect_world_for(r, RayKind::Shadow);
 
-        let r = Ray::new(point, direction);
-        let intersections = self.intersect_world(r);
+        if !self.transparent_shadows {
+            return match hit(xs) {
+                Some(h) if h.t() < distance => Pixel::black(),
+                _ => Pixel::white(),
+            };
+        }
 
-        let h = hit(intersections);
-        if let Some(hs) = h {
-            hs.t() < distance
-        } else {
-            false
+        // Each object filters the light once, where the ray enters it, and
+        // not again where it leaves. Keyed like the refraction containers.
+        let mut inside: Vec<(Option<ObjectId>, Shape)> = vec![];
+        let mut transmission = Pixel::white();
+        for i in xs {
+            if i.t() < 0.0 || i.t() >= distance {
+                continue;
+            }
+            let object = i.object();
+            let key = (i.object_id(), object.clone());
+            if let Some(index) = inside.iter().position(|x| *x == key) {
+                inside.remove(index);
+                continue;
+            }
+            inside.push(key);
+
+            let material = object.material();
+            if equal(material.transparency(), 0.0) {
+                return Pixel::black();
+            }
+            let filter = material.color_at(&object, r.position(i.t()));
+            transmission = transmission * filter * material.transparency();
         }
+
+        transmission
     }
 
     pub fn reflected_color(&self, comps: Computations, remaining: i32) -> Pixel{
@@ -121,32 +309,22 @@ impl World {
         }
 
         let reflected_ray = Ray::new(comps.over_point(), comps.reflectv());
-        let color = self.color_at(reflected_ray, remaining - 1);
+        let color = self.color_at_for(reflected_ray, RayKind::Reflection, remaining - 1);
 
         color * comps.object().material().reflective()
     }
 
     pub fn refracted_color(&self, comps: Computations, remaining: i32) -> Pixel{
         if remaining < 1 || equal(comps.object().material().transparency(), 0.0) {
-            Pixel::black()
+            return Pixel::black();
+        }
+
+        if let Some(direction) = comps.refracted_direction() {
+            let refracted_ray = Ray::new(comps.under_point(), direction);
+            self.color_at_for(refracted_ray, RayKind::Refraction, remaining - 1)
+                * comps.object().material().transparency()
         } else {
-            
-            let n_ratio = comps.n1() / comps.n2();
-            let cos_i = comps.eyev().dot(&comps.normalv());
-            let sin2_t = n_ratio.powi(2) * (1.0 - cos_i.powi(2));
-            
-            if sin2_t > 1.0 {
-                Pixel::black()
-            } else {
-                let cos_t = (1.0 - sin2_t).sqrt();
-                let direction = comps.normalv() * (n_ratio * cos_i - cos_t) - comps.eyev() * n_ratio;
-                
-                let refracted_ray = Ray::new(comps.under_point(), direction);
-                
-                let color = self.color_at(refracted_ray, remaining - 1) * comps.object().material().transparency();
-                println!("=== remaining {} ===\nn1: {}\nn2: {}\neyev: {}\nnormalv: {}\nunder point: {}\nn_ratio: {}\ncos_i: {}\nsin2_t: {}\ncost_t: {}\nrefracted direction: {}\nrefracted color: {}\n", remaining, comps.n1(), comps.n2(), comps.eyev(), comps.normalv(), comps.under_point(), n_ratio, cos_i, sin2_t, cos_t, direction, color);
-                color
-            }
+            Pixel::black()
         }
     }
 }